@@ -0,0 +1,141 @@
+//! Merger throughput on the dev backend: the old "lock, write, flush" per
+//! line versus the buffered, batched writer.
+//!
+//! Run with `cargo bench --bench merger_throughput`.
+
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use tokio_util::sync::CancellationToken;
+
+use kpl::config::{LimitOpts, SinkFilter};
+use kpl::merge::output::run_merger;
+use kpl::merge::sink::stdout::WriterSink;
+use kpl::merge::sink::{Route, Sink};
+use kpl::stream::channel::log_channel;
+use kpl::stream::status::StatusBoard;
+use kpl::stream::supervisor::{StreamBackend, StreamSupervisor};
+use kpl::types::{
+    ColorBy, ColorMode, LogEvent, OutputConfig, OutputMode, OverflowPolicy, PodCommand, PodKey,
+};
+
+const CONTAINERS: usize = 8;
+const LINES_PER_CONTAINER: u64 = 50_000;
+const ROUNDS: usize = 3;
+
+/// What `run_merger` used to do for every event.
+struct PerLineFlush(LineWriter<File>);
+
+impl Sink for PerLineFlush {
+    fn write<'a>(&'a mut self, _ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        let res = writeln!(self.0, "{line}").and_then(|_| self.0.flush());
+        Box::pin(std::future::ready(res))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
+
+fn devnull() -> File {
+    File::options()
+        .write(true)
+        .open("/dev/null")
+        .expect("open /dev/null")
+}
+
+async fn run_once(sink: Box<dyn Sink>) -> Duration {
+    let shutdown = CancellationToken::new();
+    let (log_tx, log_rx) = log_channel(2048, OverflowPolicy::Block);
+    let (fatal_tx, _fatal_rx) = tokio::sync::mpsc::channel(1);
+
+    let mut supervisor = StreamSupervisor::new(
+        log_tx,
+        fatal_tx,
+        StreamBackend::Dev {
+            rate_ms: 0,
+            max_lines: Some(LINES_PER_CONTAINER),
+        },
+        LimitOpts::default(),
+        StatusBoard::default(),
+        shutdown.clone(),
+    );
+
+    let route = Route {
+        name: "bench".to_string(),
+        output: OutputConfig {
+            mode: OutputMode::Human,
+            color_by: ColorBy::Pod,
+            color: ColorMode::Never,
+            no_color: true,
+            label_meta: false,
+            prefix: false,
+            timestamps: false,
+        },
+        filter: SinkFilter::default(),
+        sink,
+    };
+
+    let start = Instant::now();
+
+    supervisor
+        .handle_command(PodCommand::StartPod {
+            pod: PodKey {
+                namespace: "bench".to_string(),
+                name: "bench-pod".to_string(),
+                uid: "bench-uid".to_string(),
+            },
+            containers: (0..CONTAINERS).map(|i| format!("c{i}")).collect(),
+            meta: None,
+        })
+        .await;
+    // The streams hold their own senders; the merger ends once they finish.
+    drop(supervisor);
+
+    run_merger(
+        log_rx,
+        vec![route],
+        None,
+        None,
+        None,
+        Duration::from_millis(50),
+        shutdown,
+    )
+    .await
+    .expect("merger failed");
+
+    start.elapsed()
+}
+
+async fn bench(name: &str, make: impl Fn() -> Box<dyn Sink>) {
+    let total = CONTAINERS as u64 * LINES_PER_CONTAINER;
+    let mut best = Duration::MAX;
+
+    for _ in 0..ROUNDS {
+        best = best.min(run_once(make()).await);
+    }
+
+    println!(
+        "{name:<20} {total} lines in {:>8.1?}  ({:.0} lines/s)",
+        best,
+        total as f64 / best.as_secs_f64()
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    bench("per-line flush", || {
+        Box::new(PerLineFlush(LineWriter::new(devnull())))
+    })
+    .await;
+    bench("buffered batch", || {
+        Box::new(WriterSink::with_writer(devnull(), None).expect("sink"))
+    })
+    .await;
+}
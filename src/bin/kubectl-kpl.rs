@@ -0,0 +1,3 @@
+// The kpl binary under the name kubectl looks for, so `cargo install`
+// provides `kubectl kpl` as well.
+include!("../main.rs");
@@ -1,36 +1,360 @@
-use clap::{Parser, ValueEnum};
+use std::path::PathBuf;
 
-use crate::types::{ColorBy, ColorMode};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCompleter;
 
+use crate::complete::Shell;
+use crate::types::{ColorBy, ColorMode, OutputMode, OverflowPolicy};
+
+// Every flag that shapes a run can also be set as KPL_<FLAG> (e.g.
+// KPL_DEDUPE_TIMEOUT=10s). The exceptions: --kubeconfig already follows
+// $KUBECONFIG, -v follows $RUST_LOG, and the --dev flags are for testing.
 #[derive(Debug, Parser)]
 #[command(name = "kpl", version, about = "Fast multi-pod Kubernetes log tailer")]
 pub struct Cli {
-    /// Namespace
-    #[arg(short = 'n', long = "namespace", default_value = "default")]
-    pub namespace: String,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Running as `kubectl kpl`: kubectl logs defaults (no follow, last 10
+    /// lines, default container, bare messages)
+    #[arg(skip)]
+    pub plugin: bool,
+
+    /// Apply a named profile from the config files
+    #[arg(short = 'p', long = "profile", env = "KPL_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Config file to use instead of ~/.config/kpl/config.toml
+    #[arg(long = "config", env = "KPL_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Log verbosity like kubectl's -v: 0-2 info, 3-5 debug, 6 and up trace
+    #[arg(short = 'v', long = "v", global = true)]
+    pub verbosity: Option<u8>,
+
+    /// Kubeconfig context (defaults to the current context)
+    #[arg(
+        long = "context",
+        env = "KPL_CONTEXT",
+        global = true,
+        add = ArgValueCompleter::new(crate::complete::contexts)
+    )]
+    pub context: Option<String>,
+
+    /// Kubeconfig file to use instead of $KUBECONFIG or ~/.kube/config
+    #[arg(long = "kubeconfig", global = true)]
+    pub kubeconfig: Option<PathBuf>,
+
+    /// Namespace (defaults to the kubeconfig context's, like kubectl)
+    #[arg(
+        short = 'n',
+        long = "namespace",
+        env = "KPL_NAMESPACE",
+        global = true,
+        add = ArgValueCompleter::new(crate::complete::namespaces)
+    )]
+    pub namespace: Option<String>,
+
+    /// Workload whose pods to tail instead of -l (e.g. deploy/web; also
+    /// sts, ds, rs and job)
+    #[arg(
+        value_name = "KIND/NAME",
+        env = "KPL_TARGET",
+        conflicts_with = "selector",
+        add = ArgValueCompleter::new(crate::complete::targets)
+    )]
+    pub target: Option<String>,
 
     /// Label selector (e.g. app=web,tier=frontend)
-    #[arg(short = 'l', long = "selector")]
-    pub selector: String,
+    #[arg(
+        short = 'l',
+        long = "selector",
+        env = "KPL_SELECTOR",
+        global = true,
+        add = ArgValueCompleter::new(crate::complete::selectors)
+    )]
+    pub selector: Option<String>,
+
+    /// Only tail these containers
+    #[arg(
+        short = 'c',
+        long = "container",
+        env = "KPL_CONTAINER",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub containers: Vec<String>,
+
+    /// Never tail these containers (e.g. istio-proxy)
+    #[arg(
+        long = "exclude-container",
+        env = "KPL_EXCLUDE_CONTAINER",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub exclude_containers: Vec<String>,
+
+    /// Tail every container; as `kubectl kpl` only the default one is tailed otherwise
+    #[arg(
+        long = "all-containers",
+        env = "KPL_ALL_CONTAINERS",
+        default_value_t = false,
+        global = true
+    )]
+    pub all_containers: bool,
+
+    /// Keep streaming new lines (always on unless running as `kubectl kpl`)
+    #[arg(
+        short = 'f',
+        long = "follow",
+        env = "KPL_FOLLOW",
+        default_value_t = false
+    )]
+    pub follow: bool,
+
+    /// Give up when no pod has matched for this long (e.g. 30s), instead of
+    /// waiting for one to appear
+    #[arg(long = "exit-if-no-pods-after", env = "KPL_EXIT_IF_NO_PODS_AFTER")]
+    pub exit_if_no_pods_after: Option<String>,
+
+    /// Only attach to a pod once its Ready condition is true
+    #[arg(
+        long = "wait-for-ready",
+        env = "KPL_WAIT_FOR_READY",
+        default_value_t = false
+    )]
+    pub wait_for_ready: bool,
+
+    /// Exit once every matched pod has Succeeded or Failed and its logs are
+    /// written; fails if any container did (implies --follow)
+    #[arg(
+        long = "until-complete",
+        env = "KPL_UNTIL_COMPLETE",
+        default_value_t = false
+    )]
+    pub until_complete: bool,
+
+    /// With --until-complete, list each container's exit code on stderr at the end
+    #[arg(long = "summary", env = "KPL_SUMMARY", default_value_t = false)]
+    pub summary: bool,
+
+    /// Exit 0 right after writing the first log line that matches this regex,
+    /// or 4 if the pods end without one
+    #[arg(long = "until-match", env = "KPL_UNTIL_MATCH")]
+    pub until_match: Option<String>,
+
+    /// Exit 124 after this long (e.g. 30s, 5m)
+    #[arg(long = "timeout", env = "KPL_TIMEOUT")]
+    pub timeout: Option<String>,
+
+    /// Exit 3 after writing this many log lines
+    #[arg(long = "max-lines", env = "KPL_MAX_LINES")]
+    pub max_lines: Option<u64>,
+
+    /// Lines of recent log to show per container; -1 for all (`kubectl kpl`
+    /// defaults to 10)
+    #[arg(long = "tail", env = "KPL_TAIL", allow_negative_numbers = true)]
+    pub tail: Option<i64>,
+
+    /// Only show lines newer than this (e.g. 30s, 5m, 1h)
+    #[arg(long = "since", env = "KPL_SINCE")]
+    pub since: Option<String>,
+
+    /// Use the container runtime's timestamps, and show them with -o raw
+    #[arg(long = "timestamps", env = "KPL_TIMESTAMPS", default_value_t = false)]
+    pub timestamps: bool,
+
+    /// Start -o raw lines with [pod/NAME/CONTAINER]
+    #[arg(long = "prefix", env = "KPL_PREFIX", default_value_t = false)]
+    pub prefix: bool,
+
+    /// Output format [default: human, or raw as `kubectl kpl`]
+    #[arg(short = 'o', long = "output", env = "KPL_OUTPUT", value_enum)]
+    pub output: Option<OutputModeArg>,
+
+    /// Send output to TARGET[;format=MODE][;grep=RE][;grep-v=RE][;pod=RE][;container=RE]
+    /// where TARGET is stdout, file:PATH, tcp:HOST:PORT, unix:PATH or an http(s):// URL.
+    /// Repeatable, or one per line in KPL_SINK; defaults to stdout
+    #[arg(long = "sink", env = "KPL_SINK", value_delimiter = '\n')]
+    pub sinks: Vec<String>,
+
+    /// Run COMMAND (event as JSON on stdin and in KPL_* variables) or POST to
+    /// an http(s):// URL when a line matches: REGEX=COMMAND or REGEX=URL,
+    /// with `\=` for a literal '=' in the regex. A leading
+    /// [debounce=DUR,max-per-minute=N] gives the rule its own limits.
+    /// Repeatable, or one per line in KPL_ON_MATCH
+    #[arg(long = "on-match", env = "KPL_ON_MATCH", value_delimiter = '\n')]
+    pub on_match: Vec<String>,
+
+    /// Skip matches this soon after a rule last fired; 0 fires on every match
+    #[arg(
+        long = "on-match-debounce",
+        env = "KPL_ON_MATCH_DEBOUNCE",
+        default_value = "5s"
+    )]
+    pub on_match_debounce: String,
+
+    /// Most times each --on-match rule fires in a minute
+    #[arg(
+        long = "on-match-max-per-minute",
+        env = "KPL_ON_MATCH_MAX_PER_MINUTE",
+        default_value_t = 10
+    )]
+    pub on_match_max_per_minute: u32,
+
+    /// Full-screen view with scroll-back, pause, live filters and stream status
+    #[arg(long = "tui", env = "KPL_TUI", default_value_t = false)]
+    pub tui: bool,
+
+    /// Lines the --tui view keeps for scrolling back
+    #[arg(long = "scrollback", env = "KPL_SCROLLBACK", default_value_t = 10_000)]
+    pub scrollback: usize,
+
+    /// Count lines, bytes, errors, drops and attach time per container and
+    /// print them as a table on stderr at the end
+    #[arg(long = "stats", env = "KPL_STATS", default_value_t = false)]
+    pub stats: bool,
+
+    /// Show only the --stats table, redrawn on stdout, instead of the lines
+    #[arg(long = "stats-only", env = "KPL_STATS_ONLY", default_value_t = false)]
+    pub stats_only: bool,
+
+    /// Also print the --stats table this often (e.g. 10s); --stats-only
+    /// redraws every 1s by default
+    #[arg(long = "stats-interval", env = "KPL_STATS_INTERVAL")]
+    pub stats_interval: Option<String>,
+
+    /// Also write each stream to its own file under this directory
+    #[arg(long = "output-dir", env = "KPL_OUTPUT_DIR")]
+    pub output_dir: Option<PathBuf>,
+
+    /// File layout under --output-dir ({namespace}, {pod}, {container});
+    /// pod events and lifecycle lines go to a "_pod" container
+    #[arg(
+        long = "output-template",
+        env = "KPL_OUTPUT_TEMPLATE",
+        default_value = "{namespace}/{pod}/{container}.log"
+    )]
+    pub output_template: String,
+
+    /// Rotate output files once they reach this size (e.g. 512K, 100M, 1G)
+    #[arg(long = "rotate-size", env = "KPL_ROTATE_SIZE")]
+    pub rotate_size: Option<String>,
 
-    /// Emit NDJSON log events
-    #[arg(long = "json", default_value_t = false)]
-    pub json: bool,
+    /// Rotate output files after this long (e.g. 15m, 1h)
+    #[arg(long = "rotate-interval", env = "KPL_ROTATE_INTERVAL")]
+    pub rotate_interval: Option<String>,
+
+    /// Gzip output files once they are rotated
+    #[arg(
+        long = "gzip-rotated",
+        env = "KPL_GZIP_ROTATED",
+        default_value_t = false
+    )]
+    pub gzip_rotated: bool,
 
     /// Color mode: auto (tty only), always, never
-    #[arg(long = "color", value_enum, default_value_t = ColorModeArg::Auto)]
+    #[arg(long = "color", env = "KPL_COLOR", value_enum, default_value_t = ColorModeArg::Auto)]
     pub color: ColorModeArg,
 
     /// Color by: pod or container
-    #[arg(long = "color-by", value_enum, default_value_t = ColorByArg::Pod)]
+    #[arg(long = "color-by", env = "KPL_COLOR_BY", value_enum, default_value_t = ColorByArg::Pod)]
     pub color_by: ColorByArg,
 
     /// Disable colors (overrides --color)
-    #[arg(long = "no-color", default_value_t = false)]
+    #[arg(long = "no-color", env = "KPL_NO_COLOR", default_value_t = false)]
     pub no_color: bool,
 
+    /// Attach pod metadata (node, pod IP, owner, image) to every event
+    #[arg(long = "enrich", env = "KPL_ENRICH", default_value_t = false)]
+    pub enrich: bool,
+
+    /// Pod labels to attach to every event (implies --enrich)
+    #[arg(long = "show-labels", env = "KPL_SHOW_LABELS", value_delimiter = ',')]
+    pub show_labels: Vec<String>,
+
+    /// Pod annotations to attach to every event (implies --enrich)
+    #[arg(
+        long = "show-annotations",
+        env = "KPL_SHOW_ANNOTATIONS",
+        value_delimiter = ','
+    )]
+    pub show_annotations: Vec<String>,
+
+    /// Append image tag, node and selected labels to the human label (implies --enrich)
+    #[arg(long = "label-meta", env = "KPL_LABEL_META", default_value_t = false)]
+    pub label_meta: bool,
+
+    /// Don't announce pods starting, becoming ready, restarting, terminating
+    /// or going away in the stream
+    #[arg(
+        long = "no-lifecycle",
+        env = "KPL_NO_LIFECYCLE",
+        default_value_t = false
+    )]
+    pub no_lifecycle: bool,
+
+    /// Interleave Kubernetes Events about the tailed pods (scheduling, pulls,
+    /// probe failures, OOM kills, evictions)
+    #[arg(long = "events", env = "KPL_EVENTS", default_value_t = false)]
+    pub events: bool,
+
+    /// Events buffered between the log streams and the output
+    #[arg(long = "buffer", env = "KPL_BUFFER", default_value_t = 2048)]
+    pub buffer: usize,
+
+    /// What to do when the buffer is full
+    #[arg(
+        long = "on-overflow",
+        env = "KPL_ON_OVERFLOW",
+        value_enum,
+        default_value_t = OverflowArg::Block
+    )]
+    pub on_overflow: OverflowArg,
+
+    /// Per-stream line rate limit; excess lines are suppressed and counted
+    #[arg(long = "max-lines-per-sec", env = "KPL_MAX_LINES_PER_SEC")]
+    pub max_lines_per_sec: Option<u32>,
+
+    /// Keep one line in N per stream (e.g. 1/10)
+    #[arg(long = "sample", env = "KPL_SAMPLE")]
+    pub sample: Option<String>,
+
+    /// Collapse consecutive identical lines per stream into "(repeated N times)"
+    #[arg(long = "dedupe", env = "KPL_DEDUPE", default_value_t = false)]
+    pub dedupe: bool,
+
+    /// Report a run of repeated lines after this long even if it continues
+    #[arg(
+        long = "dedupe-timeout",
+        env = "KPL_DEDUPE_TIMEOUT",
+        default_value = "5s"
+    )]
+    pub dedupe_timeout: String,
+
+    /// Ignore numbers, UUIDs and hex when comparing lines for --dedupe
+    #[arg(
+        long = "dedupe-normalize",
+        env = "KPL_DEDUPE_NORMALIZE",
+        default_value_t = false
+    )]
+    pub dedupe_normalize: bool,
+
+    /// Mask bearer tokens, JWTs, AWS keys, emails, card numbers and IPs as [REDACTED:kind]
+    #[arg(
+        long = "redact-builtin",
+        env = "KPL_REDACT_BUILTIN",
+        default_value_t = false
+    )]
+    pub redact_builtin: bool,
+
+    /// Mask matches of this regex (repeatable, or one per line in KPL_REDACT);
+    /// named groups mask only the group
+    #[arg(long = "redact", env = "KPL_REDACT", value_delimiter = '\n')]
+    pub redact: Vec<String>,
+
     /// Dev mode: simulate pods without a cluster
-    #[arg(long = "dev", default_value_t = false)]
+    #[arg(long = "dev", default_value_t = false, global = true)]
     pub dev: bool,
 
     /// Dev: milliseconds between lines
@@ -40,6 +364,54 @@ pub struct Cli {
     /// Dev: lines per container per phase
     #[arg(long = "dev-lines", default_value_t = 10)]
     pub dev_lines: u64,
+
+    /// Dev: how the app container exits with --until-complete
+    #[arg(long = "dev-exit-code", default_value_t = 0)]
+    pub dev_exit_code: i32,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Print the shell completion script (e.g. `source <(kpl completions bash)`)
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// List the pods and containers a tail with these flags would attach to
+    Pods,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum OutputModeArg {
+    /// Aligned, optionally coloured lines for terminals
+    Human,
+    /// NDJSON log events
+    Json,
+    /// key=value pairs
+    Logfmt,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// OpenTelemetry logs (OTLP/JSON), one request per line
+    OtlpJson,
+    /// Message only
+    Raw,
+}
+
+impl From<OutputModeArg> for OutputMode {
+    fn from(v: OutputModeArg) -> Self {
+        match v {
+            OutputModeArg::Human => OutputMode::Human,
+            OutputModeArg::Json => OutputMode::Json,
+            OutputModeArg::Logfmt => OutputMode::Logfmt,
+            OutputModeArg::Csv => OutputMode::Csv,
+            OutputModeArg::Tsv => OutputMode::Tsv,
+            OutputModeArg::OtlpJson => OutputMode::OtlpJson,
+            OutputModeArg::Raw => OutputMode::Raw,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -60,6 +432,30 @@ impl From<ColorModeArg> for ColorMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum OverflowArg {
+    /// Slow down every stream until the output catches up
+    Block,
+    /// Discard the oldest buffered line
+    DropOldest,
+    /// Discard the incoming line
+    DropNewest,
+    /// Keep one in ten incoming lines per stream, discarding the rest
+    Sample,
+}
+
+impl From<OverflowArg> for OverflowPolicy {
+    fn from(v: OverflowArg) -> Self {
+        match v {
+            OverflowArg::Block => OverflowPolicy::Block,
+            OverflowArg::DropOldest => OverflowPolicy::DropOldest,
+            OverflowArg::DropNewest => OverflowPolicy::DropNewest,
+            OverflowArg::Sample => OverflowPolicy::Sample,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum ColorByArg {
@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsStr;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, EnvCompleter, Fish, Zsh};
+use clap_complete::CompleteEnv;
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use kube::api::ListParams;
+use kube::config::Kubeconfig;
+use kube::{Api, ResourceExt};
+
+use crate::cli::Cli;
+use crate::errors::AppResult;
+use crate::kube::target::Kind;
+
+/// Environment variable the shell sets when it asks us for completions.
+const COMPLETE_VAR: &str = "COMPLETE";
+
+/// How long a completion may wait on the API server before giving up.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Labels that are unique per rollout and only clutter `-l` completion.
+const NOISY_LABELS: &[&str] = &["pod-template-hash", "controller-revision-hash"];
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Writes the script that registers kpl with `shell`. The script calls back
+/// into this binary on every <TAB>, so flags, profiles and live cluster
+/// values stay current without regenerating it.
+pub fn write_script(shell: Shell, out: &mut dyn io::Write) -> io::Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+    };
+    let exe = std::env::current_exe()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "kpl".to_string());
+
+    completer.write_registration(COMPLETE_VAR, "kpl", "kpl", &exe, out)
+}
+
+/// Answers a completion request from the shell and exits, if this is one.
+///
+/// Must run before anything is written to stdout.
+pub fn handle_request() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// `--context`: context names from the kubeconfig.
+pub fn contexts(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Ok(kubeconfig) = read_kubeconfig() else {
+        return Vec::new();
+    };
+    let current = current.to_string_lossy();
+
+    kubeconfig
+        .contexts
+        .into_iter()
+        .filter(|c| c.name.starts_with(current.as_ref()))
+        .map(|c| CompletionCandidate::new(c.name))
+        .collect()
+}
+
+/// `-n`: namespaces from the cluster, or the ones the kubeconfig contexts
+/// name when the cluster can't be reached.
+pub fn namespaces(current: &OsStr) -> Vec<CompletionCandidate> {
+    let context = given_value(&["--context"], Some("KPL_CONTEXT"));
+    let kubeconfig = given_value(&["--kubeconfig"], None).map(PathBuf::from);
+
+    let names: BTreeSet<String> = lookup(async {
+        let client =
+            crate::kube::client::make_client(context.as_deref(), kubeconfig.as_deref()).await?;
+        let list = Api::<Namespace>::all(client)
+            .list(&ListParams::default())
+            .await?;
+        Ok(list.items.iter().map(ResourceExt::name_any).collect())
+    })
+    .unwrap_or_else(|| {
+        read_kubeconfig()
+            .map(|k| {
+                k.contexts
+                    .into_iter()
+                    .filter_map(|c| c.context?.namespace)
+                    .collect()
+            })
+            .unwrap_or_default()
+    });
+
+    let current = current.to_string_lossy();
+    names
+        .into_iter()
+        .filter(|n| n.starts_with(current.as_ref()))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// `-l`: `key=value` pairs seen on pods in the target namespace, one
+/// requirement at a time after the last comma.
+pub fn selectors(current: &OsStr) -> Vec<CompletionCandidate> {
+    let context = given_value(&["--context"], Some("KPL_CONTEXT"));
+    let kubeconfig = given_value(&["--kubeconfig"], None).map(PathBuf::from);
+    let namespace = given_value(&["-n", "--namespace"], Some("KPL_NAMESPACE"));
+
+    let Some(pods) = lookup(async {
+        let namespace =
+            namespace_or_default(namespace, context.as_deref(), kubeconfig.as_deref()).await?;
+        let client =
+            crate::kube::client::make_client(context.as_deref(), kubeconfig.as_deref()).await?;
+        let list = Api::<Pod>::namespaced(client, &namespace)
+            .list(&ListParams::default())
+            .await?;
+        Ok(list.items)
+    }) else {
+        return Vec::new();
+    };
+
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    for pod in &pods {
+        for (key, value) in pod.labels() {
+            if !NOISY_LABELS.contains(&key.as_str()) {
+                *seen.entry(format!("{key}={value}")).or_default() += 1;
+            }
+        }
+    }
+
+    let current = current.to_string_lossy();
+    let (done, partial) = match current.rfind(',') {
+        Some(i) => current.split_at(i + 1),
+        None => ("", current.as_ref()),
+    };
+    let done_keys: Vec<&str> = done
+        .split(',')
+        .filter_map(|r| r.split(['=', '!']).next())
+        .map(str::trim)
+        .collect();
+
+    seen.into_iter()
+        .filter(|(pair, _)| pair.starts_with(partial))
+        .filter(|(pair, _)| {
+            let key = pair.split('=').next().unwrap_or_default();
+            !done_keys.contains(&key)
+        })
+        .map(|(pair, count)| {
+            let help = if count == 1 {
+                "1 pod".to_string()
+            } else {
+                format!("{count} pods")
+            };
+            CompletionCandidate::new(format!("{done}{pair}")).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// `KIND/NAME`: the workload kinds, then after the slash the workloads of
+/// that kind in the target namespace.
+pub fn targets(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    let Some((kind_name, partial)) = current.split_once('/') else {
+        return Kind::ALL
+            .iter()
+            .map(|k| format!("{}/", k.short()))
+            .filter(|k| k.starts_with(current.as_ref()))
+            .map(CompletionCandidate::new)
+            .collect();
+    };
+    let Some(kind) = Kind::parse(kind_name) else {
+        return Vec::new();
+    };
+
+    let context = given_value(&["--context"], Some("KPL_CONTEXT"));
+    let kubeconfig = given_value(&["--kubeconfig"], None).map(PathBuf::from);
+    let namespace = given_value(&["-n", "--namespace"], Some("KPL_NAMESPACE"));
+
+    let names = lookup(async {
+        let namespace =
+            namespace_or_default(namespace, context.as_deref(), kubeconfig.as_deref()).await?;
+        let client =
+            crate::kube::client::make_client(context.as_deref(), kubeconfig.as_deref()).await?;
+        crate::kube::target::names(client, &namespace, kind).await
+    })
+    .unwrap_or_default();
+
+    names
+        .into_iter()
+        .filter(|n| n.starts_with(partial))
+        .map(|n| CompletionCandidate::new(format!("{kind_name}/{n}")))
+        .collect()
+}
+
+/// `-n` if given, else the kubeconfig context's, as the tail would use.
+async fn namespace_or_default(
+    namespace: Option<String>,
+    context: Option<&str>,
+    kubeconfig: Option<&Path>,
+) -> AppResult<String> {
+    match namespace {
+        Some(namespace) => Ok(namespace),
+        None => crate::kube::client::default_namespace(context, kubeconfig).await,
+    }
+}
+
+/// The `--kubeconfig` on the line, else the usual `$KUBECONFIG` lookup.
+fn read_kubeconfig() -> Result<Kubeconfig, kube::config::KubeconfigError> {
+    match given_value(&["--kubeconfig"], None) {
+        Some(path) => Kubeconfig::read_from(path),
+        None => Kubeconfig::read(),
+    }
+}
+
+/// Runs one API lookup on a throwaway runtime; completions never fail, they
+/// just come back empty.
+fn lookup<T>(fut: impl Future<Output = AppResult<T>>) -> Option<T> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .ok()?;
+    rt.block_on(async { tokio::time::timeout(LOOKUP_TIMEOUT, fut).await.ok()?.ok() })
+}
+
+/// A flag already typed on the line being completed, else its environment
+/// variable.
+fn given_value(flags: &[&str], env: Option<&str>) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let mut found = None;
+    for (i, arg) in args.iter().enumerate() {
+        for flag in flags {
+            if arg == flag {
+                found = args.get(i + 1).cloned();
+            } else if let Some(value) = arg
+                .strip_prefix(flag)
+                .and_then(|rest| rest.strip_prefix('='))
+            {
+                found = Some(value.to_string());
+            }
+        }
+    }
+
+    found
+        .filter(|v| !v.is_empty())
+        .or_else(|| std::env::var(env?).ok().filter(|v| !v.is_empty()))
+}
@@ -1,68 +1,731 @@
-use crate::cli::Cli;
-use crate::types::{ColorMode, OutputConfig, OutputMode};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::cli::{Cli, ColorModeArg};
+use crate::errors::{AppError, AppResult};
+use crate::kube::selector::{is_dns_label, LabelSelector};
+use crate::kube::target::Target;
+use crate::types::{ColorMode, LogEvent, OutputConfig, OutputMode, OverflowPolicy};
 
 #[derive(Debug, Clone)]
 pub struct RuntimeOpts {
     pub buffer: usize,
+    pub overflow: OverflowPolicy,
 }
 
 #[derive(Debug, Clone)]
 pub struct DevOpts {
     pub rate_ms: u64,
     pub lines: u64,
+    pub exit_code: i32,
 }
 
 #[derive(Debug, Clone)]
 pub struct KubeLogOpts {
+    pub context: Option<String>,
+    pub kubeconfig: Option<PathBuf>,
+    /// Only these containers, if any are given
     pub containers: Vec<String>,
+    pub exclude_containers: Vec<String>,
+    /// Only each pod's default container, like `kubectl logs` without -c
+    pub default_container_only: bool,
+    /// Keep streaming; otherwise print what is there and exit
+    pub follow: bool,
+    /// Lines of history per container; everything when unset
+    pub tail_lines: Option<i64>,
+    pub since: Option<Duration>,
+    /// Take event times from the container runtime instead of arrival
+    pub timestamps: bool,
+    /// Announce pod and container state changes in the stream while following
+    pub lifecycle: bool,
+    /// Leave pods alone until their Ready condition is true
+    pub wait_for_ready: bool,
+    /// Fail once no pod has matched for this long while following
+    pub exit_if_no_pods_after: Option<Duration>,
+    /// Stop once every matched pod has finished
+    pub until_complete: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LimitOpts {
+    pub max_lines_per_sec: Option<u32>,
+    /// Keep one line in this many
+    pub sample_every: Option<u64>,
+}
+
+/// When to end the run early.
+#[derive(Debug, Clone, Default)]
+pub struct StopOpts {
+    pub until_match: Option<Regex>,
+    pub timeout: Option<Duration>,
+    pub max_lines: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DedupeOpts {
+    /// Report a run of repeats after this long even if it is still going
+    pub timeout: Duration,
+    /// Mask numbers, UUIDs and hex before comparing
+    pub normalize: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RedactOpts {
+    /// Apply the built-in secret and PII detectors
+    pub builtin: bool,
+    /// User rules; named groups mask only the group
+    pub rules: Vec<Regex>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EnrichOpts {
+    pub enabled: bool,
+    pub labels: Vec<String>,
+    pub annotations: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TuiOpts {
+    /// Lines kept for scrolling back
+    pub scrollback: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsOpts {
+    /// How often to print the table while running; `None` prints it once,
+    /// at the end
+    pub interval: Option<Duration>,
+    /// The table replaces the log lines on stdout
+    pub only: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileOutputOpts {
+    pub dir: PathBuf,
+    pub template: String,
+    pub rotate_bytes: Option<u64>,
+    pub rotate_interval: Option<Duration>,
+    pub gzip: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum SinkTarget {
+    Stdout,
+    File(PathBuf),
+    Dir(FileOutputOpts),
+    Tcp(String),
+    Unix(PathBuf),
+    Http(String),
+}
+
+/// Which events a sink receives. Every set regex must match.
+#[derive(Debug, Clone, Default)]
+pub struct SinkFilter {
+    pub grep: Option<Regex>,
+    pub grep_v: Option<Regex>,
+    pub pod: Option<Regex>,
+    pub container: Option<Regex>,
+}
+
+impl SinkFilter {
+    pub fn matches(&self, ev: &LogEvent) -> bool {
+        self.grep.as_ref().map_or(true, |r| r.is_match(&ev.message))
+            && self
+                .grep_v
+                .as_ref()
+                .map_or(true, |r| !r.is_match(&ev.message))
+            && self.pod.as_ref().map_or(true, |r| r.is_match(&ev.pod))
+            && self
+                .container
+                .as_ref()
+                .map_or(true, |r| r.is_match(&ev.container))
+    }
+}
+
+/// One `--sink`: `TARGET[;format=MODE][;grep=RE][;grep-v=RE][;pod=RE][;container=RE]`
+/// where TARGET is `stdout`, `file:PATH`, `tcp:HOST:PORT`, `unix:PATH` or an
+/// `http(s)://` URL.
+#[derive(Debug, Clone)]
+pub struct SinkSpec {
+    pub target: SinkTarget,
+    /// Falls back to `--output` when unset
+    pub format: Option<OutputMode>,
+    pub filter: SinkFilter,
+}
+
+impl FromStr for SinkSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(';');
+        let target = parts.next().unwrap_or_default().trim();
+
+        let target = if target == "stdout" || target == "-" {
+            SinkTarget::Stdout
+        } else if target.starts_with("http://") || target.starts_with("https://") {
+            SinkTarget::Http(target.to_string())
+        } else if let Some(path) = target.strip_prefix("file:") {
+            SinkTarget::File(PathBuf::from(path))
+        } else if let Some(addr) = target.strip_prefix("tcp:") {
+            SinkTarget::Tcp(addr.to_string())
+        } else if let Some(path) = target.strip_prefix("unix:") {
+            SinkTarget::Unix(PathBuf::from(path))
+        } else {
+            return Err(format!(
+                "unknown sink target {target:?} (expected stdout, file:PATH, tcp:HOST:PORT, unix:PATH or an http(s):// URL)"
+            ));
+        };
+
+        let mut spec = SinkSpec {
+            target,
+            format: None,
+            filter: SinkFilter::default(),
+        };
+
+        for opt in parts.filter(|p| !p.is_empty()) {
+            let (key, value) = opt
+                .split_once('=')
+                .ok_or_else(|| format!("sink option {opt:?} is not key=value"))?;
+            let re = || Regex::new(value).map_err(|e| format!("sink option {key}: {e}"));
+
+            match key {
+                "format" => spec.format = Some(value.parse()?),
+                "grep" => spec.filter.grep = Some(re()?),
+                "grep-v" => spec.filter.grep_v = Some(re()?),
+                "pod" => spec.filter.pod = Some(re()?),
+                "container" => spec.filter.container = Some(re()?),
+                _ => return Err(format!("unknown sink option {key:?}")),
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
+/// What an `--on-match` rule does with a matching line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookAction {
+    /// Run with `sh -c`, the event as JSON on stdin and in `KPL_*` variables
+    Command(String),
+    /// POST the event as JSON
+    Webhook(String),
+}
+
+/// One `--on-match [LIMITS]REGEX=ACTION`. The regex ends at the first `=`
+/// not written as `\=`; an `http(s)://` action is a webhook, anything else a
+/// shell command. A leading `[debounce=DUR,max-per-minute=N]` overrides the
+/// `--on-match-*` limits for this rule.
+#[derive(Debug, Clone)]
+pub struct HookSpec {
+    pub pattern: Regex,
+    pub action: HookAction,
+    pub debounce: Option<Duration>,
+    pub max_per_minute: Option<u32>,
+}
+
+impl HookSpec {
+    /// This rule's limits, falling back to the `--on-match-*` ones.
+    pub fn limits(&self, defaults: &HookLimits) -> HookLimits {
+        HookLimits {
+            debounce: self.debounce.unwrap_or(defaults.debounce),
+            max_per_minute: self.max_per_minute.unwrap_or(defaults.max_per_minute),
+        }
+    }
+}
+
+/// Keys of the leading `[...]` block of an `--on-match` rule.
+const HOOK_LIMITS: &[&str] = &["debounce", "max-per-minute"];
+
+impl FromStr for HookSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut debounce = None;
+        let mut max_per_minute = None;
+        // `[Ee]rror=...` is a regex; only a block of known keys is limits.
+        let limits = s
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .filter(|(block, _)| {
+                block
+                    .split_once('=')
+                    .is_some_and(|(key, _)| HOOK_LIMITS.contains(&key.trim()))
+            });
+        let s = match limits {
+            Some((block, rest)) => {
+                for item in block.split(',') {
+                    let (key, value) = item
+                        .split_once('=')
+                        .ok_or_else(|| format!("expected KEY=VALUE, got {item:?}"))?;
+                    let value = value.trim();
+                    match key.trim() {
+                        "debounce" => {
+                            debounce = Some(
+                                humantime::parse_duration(value)
+                                    .map_err(|e| format!("debounce {value:?}: {e}"))?,
+                            )
+                        }
+                        "max-per-minute" => match value.parse::<u32>() {
+                            Ok(n) if n > 0 => max_per_minute = Some(n),
+                            _ => {
+                                return Err(format!(
+                                    "max-per-minute {value:?}: must be a number, at least 1"
+                                ))
+                            }
+                        },
+                        other => {
+                            return Err(format!(
+                                "unknown limit {other:?} (expected {})",
+                                HOOK_LIMITS.join(" or ")
+                            ))
+                        }
+                    }
+                }
+                rest
+            }
+            None => s,
+        };
+
+        let mut pattern = String::new();
+        let mut chars = s.char_indices();
+        let action = loop {
+            match chars.next() {
+                Some((_, '\\')) if chars.as_str().starts_with('=') => {
+                    chars.next();
+                    pattern.push('=');
+                }
+                Some((i, '=')) => break &s[i + 1..],
+                Some((_, c)) => pattern.push(c),
+                None => return Err("expected REGEX=COMMAND or REGEX=URL".into()),
+            }
+        };
+
+        if pattern.is_empty() {
+            return Err("the regex is empty".into());
+        }
+        let action = action.trim();
+        if action.is_empty() {
+            return Err("the command is empty".into());
+        }
+
+        Ok(HookSpec {
+            pattern: Regex::new(&pattern).map_err(|e| e.to_string())?,
+            action: if action.starts_with("http://") || action.starts_with("https://") {
+                HookAction::Webhook(action.to_string())
+            } else {
+                HookAction::Command(action.to_string())
+            },
+            debounce,
+            max_per_minute,
+        })
+    }
+}
+
+/// How often each `--on-match` rule may fire.
+#[derive(Debug, Clone)]
+pub struct HookLimits {
+    /// Matches this soon after the rule last fired are skipped
+    pub debounce: Duration,
+    pub max_per_minute: u32,
 }
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    pub namespace: String,
+    /// `None` follows the kubeconfig context; see [`crate::kube::client::namespace`]
+    pub namespace: Option<String>,
+    /// Empty with a target; see [`crate::kube::target::selector`]
     pub selector: String,
+    /// A workload to take the selector from (`deploy/web`)
+    pub target: Option<Target>,
     pub dev_mode: bool,
+    /// Interleave Kubernetes Events about the tailed pods
+    pub events: bool,
 
     pub output: OutputConfig,
+    pub sinks: Vec<SinkSpec>,
     pub runtime: RuntimeOpts,
     pub dev: DevOpts,
     pub kube: KubeLogOpts,
+    pub enrich: EnrichOpts,
+    pub limits: LimitOpts,
+    pub dedupe: Option<DedupeOpts>,
+    pub redact: Option<RedactOpts>,
+    /// Show the full-screen view instead of writing to stdout
+    pub tui: Option<TuiOpts>,
+    pub stats: Option<StatsOpts>,
+    /// List container exit codes after `--until-complete`
+    pub summary: bool,
+    pub stop: StopOpts,
+    pub hooks: Vec<HookSpec>,
+    pub hook_limits: HookLimits,
 }
 
 impl TryFrom<Cli> for Config {
-    type Error = std::convert::Infallible;
+    type Error = AppError;
 
-    fn try_from(cli: Cli) -> Result<Self, Self::Error> {
-        let mode = if cli.json {
-            OutputMode::Json
-        } else {
-            OutputMode::Human
+    /// Validates everything that can be checked without a cluster and
+    /// reports every problem at once.
+    fn try_from(cli: Cli) -> AppResult<Self> {
+        let mut problems = Vec::new();
+
+        let target = cli.target.as_deref().and_then(|target| {
+            target
+                .parse::<Target>()
+                .map_err(|e| problems.push(format!("target {target:?}: {e}")))
+                .ok()
+        });
+        let selector = match cli.selector {
+            Some(selector) => {
+                if let Err(e) = selector.parse::<LabelSelector>() {
+                    problems.push(format!("--selector {selector:?}: {e}"));
+                }
+                selector
+            }
+            None if cli.target.is_some() => String::new(),
+            None => {
+                problems.push(
+                    "--selector or a workload target is required (e.g. -l app=web or deploy/web)"
+                        .into(),
+                );
+                String::new()
+            }
         };
+        if let Some(namespace) = cli.namespace.as_deref().filter(|ns| !is_dns_label(ns)) {
+            problems.push(format!(
+                "--namespace {namespace:?}: must be a lowercase RFC 1123 label (a-z, 0-9 and '-', at most 63 characters)"
+            ));
+        }
+        if cli.buffer == 0 {
+            problems.push("--buffer: must be at least 1".into());
+        }
+        if cli.max_lines_per_sec == Some(0) {
+            problems.push("--max-lines-per-sec: must be at least 1".into());
+        }
+        // Zero is a flood for tests and benchmarks; without a line limit it
+        // never ends.
+        if cli.dev && cli.dev_rate_ms == 0 && cli.dev_lines == 0 {
+            problems.push("--dev-rate-ms 0 needs a --dev-lines limit".into());
+        }
 
-        let color = if cli.json {
-            ColorMode::Never
-        } else {
+        if cli.tail.is_some_and(|n| n < -1) {
+            problems.push("--tail: must be -1 (everything) or more".into());
+        }
+        if cli.all_containers && !cli.containers.is_empty() {
+            problems.push("--all-containers conflicts with -c".into());
+        }
+
+        let mode: OutputMode = match cli.output {
+            Some(mode) => mode.into(),
+            None if cli.plugin => OutputMode::Raw,
+            None => OutputMode::Human,
+        };
+        if matches!(cli.color, ColorModeArg::Always) {
+            if cli.no_color {
+                problems.push("--color always conflicts with --no-color".into());
+            }
+            if mode != OutputMode::Human {
+                problems.push(format!(
+                    "--color always only applies to human output, not -o {mode}"
+                ));
+            }
+        }
+        if cli.tui {
+            if cli.scrollback == 0 {
+                problems.push("--scrollback: must be at least 1".into());
+            }
+            if cli.sinks.iter().any(|s| {
+                let target = s.split(';').next().unwrap_or_default().trim();
+                target == "stdout" || target == "-"
+            }) {
+                problems.push("--tui conflicts with --sink stdout".into());
+            }
+        }
+        if cli.stats_only {
+            if cli.tui {
+                problems.push("--stats-only conflicts with --tui".into());
+            }
+            if cli.sinks.iter().any(|s| {
+                let target = s.split(';').next().unwrap_or_default().trim();
+                target == "stdout" || target == "-"
+            }) {
+                problems.push("--stats-only conflicts with --sink stdout".into());
+            }
+        }
+        let stats = cli.stats || cli.stats_only;
+        if cli.stats_interval.is_some() && !stats {
+            problems.push("--stats-interval requires --stats or --stats-only".into());
+        }
+        if cli.max_lines == Some(0) {
+            problems.push("--max-lines: must be at least 1".into());
+        }
+        if cli.on_match_max_per_minute == 0 {
+            problems.push("--on-match-max-per-minute: must be at least 1".into());
+        }
+        if cli.summary && !cli.until_complete {
+            problems.push("--summary requires --until-complete".into());
+        }
+        if cli.output_dir.is_none() {
+            for (set, flag) in [
+                (cli.rotate_size.is_some(), "--rotate-size"),
+                (cli.rotate_interval.is_some(), "--rotate-interval"),
+                (cli.gzip_rotated, "--gzip-rotated"),
+            ] {
+                if set {
+                    problems.push(format!("{flag} requires --output-dir"));
+                }
+            }
+        }
+
+        let mut duration = |flag: &str, value: &str| match humantime::parse_duration(value) {
+            Ok(d) if !d.is_zero() => Some(d),
+            Ok(_) => {
+                problems.push(format!("{flag} {value:?}: must be longer than zero"));
+                None
+            }
+            Err(e) => {
+                problems.push(format!("{flag} {value:?}: {e}"));
+                None
+            }
+        };
+        let rotate_interval = cli
+            .rotate_interval
+            .as_deref()
+            .and_then(|v| duration("--rotate-interval", v));
+        let dedupe_timeout = duration("--dedupe-timeout", &cli.dedupe_timeout);
+        let since = cli.since.as_deref().and_then(|v| duration("--since", v));
+        let exit_if_no_pods_after = cli
+            .exit_if_no_pods_after
+            .as_deref()
+            .and_then(|v| duration("--exit-if-no-pods-after", v));
+        let timeout = cli
+            .timeout
+            .as_deref()
+            .and_then(|v| duration("--timeout", v));
+        let stats_interval = cli
+            .stats_interval
+            .as_deref()
+            .and_then(|v| duration("--stats-interval", v));
+
+        let rotate_bytes = cli
+            .rotate_size
+            .as_deref()
+            .and_then(|v| match parse_size(v) {
+                Ok(n) => Some(n),
+                Err(e) => {
+                    problems.push(format!("--rotate-size {v:?}: {e}"));
+                    None
+                }
+            });
+        let sample_every = cli.sample.as_deref().and_then(|v| match parse_sample(v) {
+            Ok(n) => Some(n),
+            Err(e) => {
+                problems.push(format!("--sample {v:?}: {e}"));
+                None
+            }
+        });
+
+        let mut redact = Vec::with_capacity(cli.redact.len());
+        for pattern in &cli.redact {
+            match Regex::new(pattern) {
+                Ok(re) => redact.push(re),
+                Err(e) => problems.push(format!("--redact {pattern:?}: {e}")),
+            }
+        }
+
+        let until_match =
+            cli.until_match
+                .as_deref()
+                .and_then(|pattern| match Regex::new(pattern) {
+                    Ok(re) => Some(re),
+                    Err(e) => {
+                        problems.push(format!("--until-match {pattern:?}: {e}"));
+                        None
+                    }
+                });
+
+        // Zero turns debouncing off.
+        let debounce = match humantime::parse_duration(&cli.on_match_debounce) {
+            Ok(d) => d,
+            Err(e) => {
+                problems.push(format!(
+                    "--on-match-debounce {:?}: {e}",
+                    cli.on_match_debounce
+                ));
+                Duration::ZERO
+            }
+        };
+        let mut hooks = Vec::with_capacity(cli.on_match.len());
+        for rule in &cli.on_match {
+            match rule.parse::<HookSpec>() {
+                Ok(hook) => hooks.push(hook),
+                Err(e) => problems.push(format!("--on-match {rule:?}: {e}")),
+            }
+        }
+
+        let mut sinks = Vec::with_capacity(cli.sinks.len() + 1);
+        for spec in &cli.sinks {
+            match spec.parse::<SinkSpec>() {
+                Ok(spec) => sinks.push(spec),
+                Err(e) => problems.push(format!("--sink {spec:?}: {e}")),
+            }
+        }
+
+        if !problems.is_empty() {
+            let mut msg = String::from("invalid configuration:");
+            for problem in &problems {
+                msg.push_str("\n  - ");
+                msg.push_str(&problem.replace('\n', "\n    "));
+            }
+            return Err(AppError::Cli(msg));
+        }
+
+        let color = if mode == OutputMode::Human {
             cli.color.into()
+        } else {
+            ColorMode::Never
         };
 
+        let follow = cli.follow || !cli.plugin || cli.until_complete;
+
+        if sinks.is_empty() && !cli.tui && !cli.stats_only {
+            sinks.push(SinkSpec {
+                target: SinkTarget::Stdout,
+                format: None,
+                filter: SinkFilter::default(),
+            });
+        }
+        if let Some(dir) = cli.output_dir {
+            sinks.push(SinkSpec {
+                target: SinkTarget::Dir(FileOutputOpts {
+                    dir,
+                    template: cli.output_template,
+                    rotate_bytes,
+                    rotate_interval,
+                    gzip: cli.gzip_rotated,
+                }),
+                format: None,
+                filter: SinkFilter::default(),
+            });
+        }
+
         Ok(Config {
             namespace: cli.namespace,
-            selector: cli.selector,
+            selector,
+            target,
             dev_mode: cli.dev,
+            events: cli.events,
             output: OutputConfig {
                 mode,
                 color_by: cli.color_by.into(),
                 color,
                 no_color: cli.no_color,
+                label_meta: cli.label_meta,
+                prefix: cli.prefix,
+                timestamps: cli.timestamps,
+            },
+            sinks,
+            runtime: RuntimeOpts {
+                buffer: cli.buffer,
+                overflow: cli.on_overflow.into(),
             },
-            runtime: RuntimeOpts { buffer: 2048 },
             dev: DevOpts {
                 rate_ms: cli.dev_rate_ms,
                 lines: cli.dev_lines,
+                exit_code: cli.dev_exit_code,
             },
             kube: KubeLogOpts {
-                containers: Vec::new(),
+                context: cli.context,
+                kubeconfig: cli.kubeconfig,
+                default_container_only: cli.plugin
+                    && !cli.all_containers
+                    && cli.containers.is_empty(),
+                containers: cli.containers,
+                exclude_containers: cli.exclude_containers,
+                follow,
+                // kubectl logs shows the last 10 lines per pod with a selector.
+                tail_lines: match cli.tail.or(cli.plugin.then_some(10)) {
+                    Some(-1) | None => None,
+                    n => n,
+                },
+                since,
+                timestamps: cli.timestamps,
+                lifecycle: follow && !cli.no_lifecycle,
+                wait_for_ready: cli.wait_for_ready,
+                exit_if_no_pods_after,
+                until_complete: cli.until_complete,
+            },
+            enrich: EnrichOpts {
+                enabled: cli.enrich
+                    || cli.label_meta
+                    || !cli.show_labels.is_empty()
+                    || !cli.show_annotations.is_empty(),
+                labels: cli.show_labels,
+                annotations: cli.show_annotations,
+            },
+            limits: LimitOpts {
+                max_lines_per_sec: cli.max_lines_per_sec,
+                sample_every,
+            },
+            dedupe: cli.dedupe.then_some(DedupeOpts {
+                timeout: dedupe_timeout.expect("validated above"),
+                normalize: cli.dedupe_normalize,
+            }),
+            redact: (cli.redact_builtin || !redact.is_empty()).then_some(RedactOpts {
+                builtin: cli.redact_builtin,
+                rules: redact,
+            }),
+            tui: cli.tui.then_some(TuiOpts {
+                scrollback: cli.scrollback,
+            }),
+            stats: stats.then(|| StatsOpts {
+                interval: stats_interval.or(cli.stats_only.then_some(Duration::from_secs(1))),
+                only: cli.stats_only,
+            }),
+            summary: cli.summary,
+            stop: StopOpts {
+                until_match,
+                timeout,
+                max_lines: cli.max_lines,
+            },
+            hooks,
+            hook_limits: HookLimits {
+                debounce,
+                max_per_minute: cli.on_match_max_per_minute,
             },
         })
     }
 }
+
+/// `1/N` or just `N`: keep one line in N.
+fn parse_sample(s: &str) -> Result<u64, String> {
+    let n = s.strip_prefix("1/").unwrap_or(s);
+    match n.parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err("expected 1/N with N at least 1".to_string()),
+    }
+}
+
+/// Bytes, with an optional K, M or G suffix (powers of 1024).
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+
+    let n: u64 = num
+        .parse()
+        .map_err(|_| "expected a size such as 512K or 100M".to_string())?;
+    let mult: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KI" | "KIB" => 1 << 10,
+        "M" | "MB" | "MI" | "MIB" => 1 << 20,
+        "G" | "GB" | "GI" | "GIB" => 1 << 30,
+        _ => return Err(format!("unknown unit {unit:?} (expected K, M or G)")),
+    };
+
+    n.checked_mul(mult)
+        .filter(|&b| b > 0)
+        .ok_or_else(|| "must be more than zero and fit in 64 bits".to_string())
+}
@@ -0,0 +1,66 @@
+use time::OffsetDateTime;
+use tokio::time::{sleep, Duration};
+
+use crate::stream::channel::LogTx;
+use crate::types::{ClusterEvent, EventKind, LogEvent};
+
+/// Simulated Events for the dev pod, on the same timeline as
+/// [`crate::dev::pods::spawn_dev_pods`]: scheduled and started, then
+/// restarted after five seconds when following.
+pub fn spawn_dev_events(namespace: String, follow: bool, tx: LogTx) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let ev = |container: &str, event_type: &str, reason: &str, note: String, reporter: &str| {
+            LogEvent {
+                ts: OffsetDateTime::now_utc(),
+                namespace: namespace.clone(),
+                pod: "dev-pod-1".to_string(),
+                container: container.to_string(),
+                message: note,
+                meta: None,
+                kind: EventKind::Event(Box::new(ClusterEvent {
+                    event_type: event_type.to_string(),
+                    reason: reason.to_string(),
+                    count: 1,
+                    reporter: Some(reporter.to_string()),
+                })),
+            }
+        };
+
+        let started = [
+            ev(
+                "",
+                "Normal",
+                "Scheduled",
+                format!("Successfully assigned {namespace}/dev-pod-1 to dev-node-1"),
+                "default-scheduler",
+            ),
+            ev(
+                "app",
+                "Normal",
+                "Started",
+                "Started container app".to_string(),
+                "kubelet",
+            ),
+        ];
+        for e in started {
+            if tx.send(e).await.is_err() {
+                return;
+            }
+        }
+
+        if !follow {
+            return;
+        }
+
+        sleep(Duration::from_secs(5)).await;
+        let _ = tx
+            .send(ev(
+                "app",
+                "Warning",
+                "BackOff",
+                "Back-off restarting failed container app".to_string(),
+                "kubelet",
+            ))
+            .await;
+    })
+}
@@ -1,16 +1,30 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use k8s_openapi::api::core::v1::{Container, ContainerStatus, Pod, PodSpec, PodStatus};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use k8s_openapi::chrono::Utc;
 use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
+use crate::config::{EnrichOpts, KubeLogOpts};
 use crate::errors::AppResult;
-use crate::types::{PodCommand, PodKey};
+use crate::podwatch::outcome::{ContainerExit, Outcome};
+use crate::podwatch::watcher::pick_containers;
+use crate::types::{PodCommand, PodKey, PodMeta};
 
 pub fn spawn_dev_pods(
     namespace: String,
+    opts: KubeLogOpts,
+    enrich: EnrichOpts,
+    exit_code: i32,
     tx: mpsc::Sender<PodCommand>,
-) -> tokio::task::JoinHandle<AppResult<()>> {
+) -> tokio::task::JoinHandle<AppResult<Option<Outcome>>> {
     tokio::spawn(async move {
         tracing::info!("starting dev-mode pod source");
 
+        let containers = pick_containers(&dev_pod(&namespace), &opts);
+
         let pod = PodKey {
             namespace: namespace.clone(),
             name: "dev-pod-1".to_string(),
@@ -19,11 +33,34 @@ pub fn spawn_dev_pods(
 
         tx.send(PodCommand::StartPod {
             pod: pod.clone(),
-            containers: vec!["app".to_string(), "sidecar".to_string()],
+            containers: containers.clone(),
+            meta: dev_meta(&enrich, "v1"),
         })
         .await
         .ok();
 
+        if opts.until_complete {
+            // The simulated job: every container exits once its lines are
+            // written, the app one with --dev-exit-code.
+            return Ok(Some(Outcome {
+                containers: containers
+                    .into_iter()
+                    .map(|container| {
+                        let exit_code = if container == "app" { exit_code } else { 0 };
+                        ContainerExit {
+                            pod: pod.name.clone(),
+                            container,
+                            reason: if exit_code == 0 { "Completed" } else { "Error" }.to_string(),
+                            exit_code,
+                        }
+                    })
+                    .collect(),
+            }));
+        }
+        if !opts.follow {
+            return Ok(None);
+        }
+
         sleep(Duration::from_secs(5)).await;
 
         tracing::info!("simulating pod restart");
@@ -37,7 +74,8 @@ pub fn spawn_dev_pods(
 
         tx.send(PodCommand::StartPod {
             pod: pod2,
-            containers: vec!["app".to_string(), "sidecar".to_string()],
+            containers,
+            meta: dev_meta(&enrich, "v2"),
         })
         .await
         .ok();
@@ -46,6 +84,78 @@ pub fn spawn_dev_pods(
 
         tracing::info!("dev-mode finished");
 
-        Ok(())
+        Ok(None)
     })
 }
+
+/// The simulated pod as the API server would describe it, so `kpl pods
+/// --dev` goes through the same filtering as a real cluster.
+pub fn dev_pod(namespace: &str) -> Pod {
+    let names = ["app", "sidecar"];
+
+    Pod {
+        metadata: ObjectMeta {
+            name: Some("dev-pod-1".to_string()),
+            namespace: Some(namespace.to_string()),
+            uid: Some("dev-uid-1".to_string()),
+            labels: Some(BTreeMap::from([("app".to_string(), "web".to_string())])),
+            creation_timestamp: Some(Time(Utc::now())),
+            ..Default::default()
+        },
+        spec: Some(PodSpec {
+            containers: names
+                .iter()
+                .map(|name| Container {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            node_name: Some("dev-node-1".to_string()),
+            ..Default::default()
+        }),
+        status: Some(PodStatus {
+            phase: Some("Running".to_string()),
+            container_statuses: Some(
+                names
+                    .iter()
+                    .map(|name| ContainerStatus {
+                        name: name.to_string(),
+                        ready: true,
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }),
+    }
+}
+
+fn dev_meta(enrich: &EnrichOpts, version: &str) -> Option<Arc<PodMeta>> {
+    if !enrich.enabled {
+        return None;
+    }
+
+    let labels: BTreeMap<String, String> = [("app", "web"), ("version", version)]
+        .into_iter()
+        .filter(|(k, _)| enrich.labels.iter().any(|want| want == k))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    let images = [
+        ("app", format!("registry.local/web:{version}")),
+        ("sidecar", "registry.local/proxy:1.0".to_string()),
+    ]
+    .into_iter()
+    .map(|(c, i)| (c.to_string(), i))
+    .collect();
+
+    Some(Arc::new(PodMeta {
+        node: Some("dev-node-1".to_string()),
+        pod_ip: Some("10.0.0.1".to_string()),
+        replica_set: Some("dev-pod-7d4b9c".to_string()),
+        deployment: Some("dev-pod".to_string()),
+        labels,
+        annotations: BTreeMap::new(),
+        images,
+    }))
+}
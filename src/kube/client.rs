@@ -1,6 +1,68 @@
-use crate::errors::AppResult;
+use std::path::Path;
 
-pub async fn make_client() -> AppResult<kube::Client> {
-    let client = kube::Client::try_default().await?;
-    Ok(client)
+use kube::config::{KubeConfigOptions, Kubeconfig};
+
+use crate::config::Config;
+use crate::errors::{AppError, AppResult};
+
+pub async fn make_client(
+    context: Option<&str>,
+    kubeconfig: Option<&Path>,
+) -> AppResult<kube::Client> {
+    Ok(kube::Client::try_from(
+        client_config(context, kubeconfig).await?,
+    )?)
+}
+
+/// The namespace to tail: `-n`, else the one the kubeconfig context (or the
+/// in-cluster service account) sets, as `kubectl logs` does.
+pub async fn namespace(config: &Config) -> AppResult<String> {
+    if let Some(namespace) = &config.namespace {
+        return Ok(namespace.clone());
+    }
+    if config.dev_mode {
+        return Ok("default".to_string());
+    }
+    default_namespace(
+        config.kube.context.as_deref(),
+        config.kube.kubeconfig.as_deref(),
+    )
+    .await
+}
+
+/// The namespace the selected kubeconfig context points at; `default` when
+/// it names none.
+pub async fn default_namespace(
+    context: Option<&str>,
+    kubeconfig: Option<&Path>,
+) -> AppResult<String> {
+    Ok(client_config(context, kubeconfig).await?.default_namespace)
+}
+
+async fn client_config(
+    context: Option<&str>,
+    kubeconfig: Option<&Path>,
+) -> AppResult<kube::Config> {
+    if context.is_none() && kubeconfig.is_none() {
+        return kube::Config::infer()
+            .await
+            .map_err(|e| AppError::Other(format!("kubeconfig: {e}")));
+    }
+
+    let opts = KubeConfigOptions {
+        context: context.map(str::to_string),
+        ..Default::default()
+    };
+    match kubeconfig {
+        Some(path) => {
+            let file = Kubeconfig::read_from(path)
+                .map_err(|e| AppError::Other(format!("kubeconfig {}: {e}", path.display())))?;
+            kube::Config::from_custom_kubeconfig(file, &opts).await
+        }
+        None => kube::Config::from_kubeconfig(&opts).await,
+    }
+    .map_err(|e| match context {
+        Some(context) => AppError::Other(format!("kubeconfig context {context:?}: {e}")),
+        None => AppError::Other(format!("kubeconfig: {e}")),
+    })
 }
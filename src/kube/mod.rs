@@ -1 +1,3 @@
 pub mod client;
+pub mod selector;
+pub mod target;
@@ -0,0 +1,214 @@
+use std::str::FromStr;
+
+/// A label selector parsed with the same rules the API server applies, so a
+/// typo is reported before we connect instead of as a 400 from the watcher.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    pub requirements: Vec<Requirement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Requirement {
+    pub key: String,
+    pub op: Op,
+    pub values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Exists,
+    DoesNotExist,
+    Equals,
+    NotEquals,
+    In,
+    NotIn,
+    GreaterThan,
+    LessThan,
+}
+
+impl FromStr for LabelSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(LabelSelector::default());
+        }
+
+        let requirements = split_top_level(s)?
+            .into_iter()
+            .map(parse_requirement)
+            .collect::<Result<_, _>>()?;
+
+        Ok(LabelSelector { requirements })
+    }
+}
+
+/// Splits on commas that aren't inside an `in (...)` value list.
+fn split_top_level(s: &str) -> Result<Vec<&str>, String> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' if depth > 0 => return Err("nested '('".into()),
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err("unmatched ')'".into()),
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return Err("unclosed '('".into());
+    }
+    parts.push(&s[start..]);
+
+    Ok(parts)
+}
+
+fn parse_requirement(s: &str) -> Result<Requirement, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty requirement (stray ',')".into());
+    }
+
+    if let Some(key) = s.strip_prefix('!') {
+        let key = key.trim();
+        check_key(key)?;
+        return Ok(Requirement {
+            key: key.to_string(),
+            op: Op::DoesNotExist,
+            values: Vec::new(),
+        });
+    }
+
+    let end = s
+        .find(|c: char| c.is_whitespace() || "=!<>(".contains(c))
+        .unwrap_or(s.len());
+    let (key, rest) = s.split_at(end);
+    check_key(key)?;
+    let rest = rest.trim_start();
+
+    let requirement = |op, values| Requirement {
+        key: key.to_string(),
+        op,
+        values,
+    };
+
+    if rest.is_empty() {
+        return Ok(requirement(Op::Exists, Vec::new()));
+    }
+
+    for (token, op) in [
+        ("!=", Op::NotEquals),
+        ("==", Op::Equals),
+        ("=", Op::Equals),
+        (">", Op::GreaterThan),
+        ("<", Op::LessThan),
+    ] {
+        if let Some(value) = rest.strip_prefix(token) {
+            let value = value.trim();
+            check_value(value)?;
+            if matches!(op, Op::GreaterThan | Op::LessThan) && value.parse::<i64>().is_err() {
+                return Err(format!("{key}{token}{value}: value must be an integer"));
+            }
+            return Ok(requirement(op, vec![value.to_string()]));
+        }
+    }
+
+    for (word, op) in [("notin", Op::NotIn), ("in", Op::In)] {
+        let Some(list) = rest.strip_prefix(word) else {
+            continue;
+        };
+        let list = list.trim_start();
+        let Some(inner) = list.strip_prefix('(').and_then(|l| l.strip_suffix(')')) else {
+            // `inx` is a bad operator, not `in` followed by junk.
+            if !list.starts_with(|c: char| c.is_alphanumeric()) {
+                return Err(format!("{key} {word}: expected a (value, ...) list"));
+            }
+            continue;
+        };
+
+        let values: Vec<String> = inner.split(',').map(|v| v.trim().to_string()).collect();
+        if values.iter().all(String::is_empty) {
+            return Err(format!("{key} {word} (): needs at least one value"));
+        }
+        for value in &values {
+            check_value(value)?;
+        }
+        return Ok(requirement(op, values));
+    }
+
+    Err(format!(
+        "{s:?}: expected =, ==, !=, in (...), notin (...) or nothing after key {key:?}"
+    ))
+}
+
+/// `[prefix/]name`, where the prefix is a DNS subdomain.
+fn check_key(key: &str) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("missing label key".into());
+    }
+
+    let name = match key.split_once('/') {
+        Some((prefix, name)) => {
+            if prefix.is_empty() || prefix.len() > 253 || !is_dns_subdomain(prefix) {
+                return Err(format!(
+                    "label key {key:?}: prefix must be a lowercase DNS subdomain"
+                ));
+            }
+            name
+        }
+        None => key,
+    };
+
+    if name.is_empty() || name.len() > 63 || !is_label_name(name) {
+        return Err(format!(
+            "label key {key:?}: name must be 1-63 alphanumerics, '-', '_' or '.', \
+             starting and ending with an alphanumeric"
+        ));
+    }
+    Ok(())
+}
+
+fn check_value(value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    if value.len() > 63 || !is_label_name(value) {
+        return Err(format!(
+            "label value {value:?}: must be at most 63 alphanumerics, '-', '_' or '.', \
+             starting and ending with an alphanumeric"
+        ));
+    }
+    Ok(())
+}
+
+fn is_label_name(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    bytes.first().is_some_and(u8::is_ascii_alphanumeric)
+        && bytes.last().is_some_and(u8::is_ascii_alphanumeric)
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+fn is_dns_subdomain(s: &str) -> bool {
+    s.split('.').all(is_dns_label)
+}
+
+/// RFC 1123 label: what namespaces and container names must be.
+pub fn is_dns_label(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    !s.is_empty()
+        && s.len() <= 63
+        && bytes.first().is_some_and(|b| b.is_ascii_alphanumeric())
+        && bytes.last().is_some_and(|b| b.is_ascii_alphanumeric())
+        && bytes
+            .iter()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || *b == b'-')
+}
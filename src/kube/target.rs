@@ -0,0 +1,189 @@
+use std::fmt;
+use std::str::FromStr;
+
+use k8s_openapi::api::apps::v1::{DaemonSet, Deployment, ReplicaSet, StatefulSet};
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector as WorkloadSelector;
+use kube::api::ListParams;
+use kube::{Api, Client, ResourceExt};
+
+use crate::config::Config;
+use crate::errors::{AppError, AppResult};
+
+/// Workload kinds a target may name, each with the spellings kubectl takes
+/// (`deploy/web`, `deployment/web`, `deployments/web`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+    ReplicaSet,
+    Job,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 5] = [
+        Kind::Deployment,
+        Kind::StatefulSet,
+        Kind::DaemonSet,
+        Kind::ReplicaSet,
+        Kind::Job,
+    ];
+
+    /// The short name completions offer.
+    pub fn short(self) -> &'static str {
+        match self {
+            Kind::Deployment => "deploy",
+            Kind::StatefulSet => "sts",
+            Kind::DaemonSet => "ds",
+            Kind::ReplicaSet => "rs",
+            Kind::Job => "job",
+        }
+    }
+
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            Kind::Deployment => &["deploy", "deployment", "deployments"],
+            Kind::StatefulSet => &["sts", "statefulset", "statefulsets"],
+            Kind::DaemonSet => &["ds", "daemonset", "daemonsets"],
+            Kind::ReplicaSet => &["rs", "replicaset", "replicasets"],
+            Kind::Job => &["job", "jobs"],
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Kind> {
+        let s = s.to_ascii_lowercase();
+        Kind::ALL
+            .into_iter()
+            .find(|k| k.names().contains(&s.as_str()))
+    }
+}
+
+/// A `kind/name` argument: tail the pods the workload selects, like
+/// `kubectl logs deploy/web`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub kind: Kind,
+    pub name: String,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, name) = s
+            .split_once('/')
+            .ok_or("expected KIND/NAME (e.g. deploy/web)")?;
+        let kind = Kind::parse(kind).ok_or_else(|| {
+            let kinds: Vec<&str> = Kind::ALL.iter().map(|k| k.short()).collect();
+            format!("unknown kind {kind:?} (one of {})", kinds.join(", "))
+        })?;
+        if name.is_empty() || name.contains('/') {
+            return Err("expected KIND/NAME (e.g. deploy/web)".into());
+        }
+        Ok(Target {
+            kind,
+            name: name.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.kind.short(), self.name)
+    }
+}
+
+/// The label selector to tail with: `-l`, or the one the target workload
+/// selects its pods by.
+pub async fn selector(config: &Config, namespace: &str) -> AppResult<String> {
+    let Some(target) = config.target.as_ref().filter(|_| !config.dev_mode) else {
+        return Ok(config.selector.clone());
+    };
+    let client = crate::kube::client::make_client(
+        config.kube.context.as_deref(),
+        config.kube.kubeconfig.as_deref(),
+    )
+    .await?;
+
+    let selector = match target.kind {
+        Kind::Deployment => Api::<Deployment>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?
+            .spec
+            .map(|s| s.selector),
+        Kind::StatefulSet => Api::<StatefulSet>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?
+            .spec
+            .map(|s| s.selector),
+        Kind::DaemonSet => Api::<DaemonSet>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?
+            .spec
+            .map(|s| s.selector),
+        Kind::ReplicaSet => Api::<ReplicaSet>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?
+            .spec
+            .map(|s| s.selector),
+        Kind::Job => Api::<Job>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?
+            .spec
+            .and_then(|s| s.selector),
+    };
+
+    match selector.map(|s| selector_string(&s)) {
+        Some(selector) if !selector.is_empty() => Ok(selector),
+        // An empty selector would tail every pod in the namespace.
+        _ => Err(AppError::Other(format!("{target} has no pod selector"))),
+    }
+}
+
+/// Names of the workloads of one kind in `namespace`, for completion.
+pub async fn names(client: Client, namespace: &str, kind: Kind) -> AppResult<Vec<String>> {
+    let lp = ListParams::default();
+    let names = match kind {
+        Kind::Deployment => names_of(Api::<Deployment>::namespaced(client, namespace), &lp).await?,
+        Kind::StatefulSet => {
+            names_of(Api::<StatefulSet>::namespaced(client, namespace), &lp).await?
+        }
+        Kind::DaemonSet => names_of(Api::<DaemonSet>::namespaced(client, namespace), &lp).await?,
+        Kind::ReplicaSet => names_of(Api::<ReplicaSet>::namespaced(client, namespace), &lp).await?,
+        Kind::Job => names_of(Api::<Job>::namespaced(client, namespace), &lp).await?,
+    };
+    Ok(names)
+}
+
+async fn names_of<K>(api: Api<K>, lp: &ListParams) -> AppResult<Vec<String>>
+where
+    K: kube::Resource + Clone + serde::de::DeserializeOwned + fmt::Debug,
+{
+    Ok(api
+        .list(lp)
+        .await?
+        .iter()
+        .map(ResourceExt::name_any)
+        .collect())
+}
+
+/// Renders a workload's selector in `-l` syntax.
+pub fn selector_string(selector: &WorkloadSelector) -> String {
+    let mut parts: Vec<String> = selector
+        .match_labels
+        .iter()
+        .flatten()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect();
+    for expr in selector.match_expressions.iter().flatten() {
+        let values = expr.values.clone().unwrap_or_default().join(",");
+        parts.push(match expr.operator.as_str() {
+            "In" => format!("{} in ({values})", expr.key),
+            "NotIn" => format!("{} notin ({values})", expr.key),
+            "DoesNotExist" => format!("!{}", expr.key),
+            _ => expr.key.clone(),
+        });
+    }
+    parts.join(",")
+}
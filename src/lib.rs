@@ -1,4 +1,5 @@
 pub mod cli;
+pub mod complete;
 pub mod config;
 pub mod dev;
 pub mod errors;
@@ -6,41 +7,123 @@ pub mod kube;
 pub mod logging;
 pub mod merge;
 pub mod podwatch;
+pub mod settings;
 pub mod shutdown;
 pub mod stream;
+pub mod tui;
 pub mod types;
 
+use std::io::IsTerminal;
+
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
 use crate::config::Config;
-use crate::errors::AppResult;
-use crate::types::PodCommand;
+use crate::errors::{AppError, AppResult};
+use crate::merge::sink::Route;
+use crate::shutdown::ShutdownReason;
+use crate::stream::status::{StatusBoard, StreamState};
+use crate::types::{ColorMode, OutputMode, PodCommand};
 
-pub async fn run(config: Config) -> AppResult<()> {
+/// Tails until a stop condition, a signal or the end of the pods, and says
+/// which one ended it.
+pub async fn run(config: Config) -> AppResult<ShutdownReason> {
     let shutdown = crate::shutdown::Shutdown::new();
     let shutdown_token: CancellationToken = shutdown.token();
     let monitor_shutdown: CancellationToken = shutdown_token.clone();
 
+    if config.tui.is_some() && !std::io::stdout().is_terminal() {
+        return Err(AppError::Cli("--tui needs a terminal".into()));
+    }
+
+    let namespace = crate::kube::client::namespace(&config).await?;
+    let selector = crate::kube::target::selector(&config, &namespace).await?;
+    let until_match = config.stop.until_match.is_some();
+
+    let mut routes = crate::merge::sink::open_routes(config.sinks.clone(), &config.output).await?;
+    routes.extend(crate::merge::sink::hook_routes(
+        &config.hooks,
+        &config.hook_limits,
+        &config.output,
+    )?);
+    let status = StatusBoard::default();
+    let stats = status.stats();
+
+    let tui_task = config.tui.as_ref().map(|tui| {
+        let (tx, rx) = mpsc::channel(config.runtime.buffer);
+        let mut output = config.output.clone();
+        // The view renders events itself; keep the unused line cheap.
+        output.mode = OutputMode::Raw;
+        output.color = ColorMode::Never;
+        routes.push(Route {
+            name: "tui".to_string(),
+            output,
+            filter: Default::default(),
+            sink: Box::new(crate::merge::sink::channel::ChannelSink::new(tx)),
+        });
+        tokio::spawn(crate::tui::run(
+            rx,
+            status.subscribe(),
+            status.subscribe_watcher(),
+            tui.scrollback,
+            shutdown_token.clone(),
+        ))
+    });
+
+    let stats_stop = CancellationToken::new();
+    let stats_task = config.stats.clone().map(|opts| {
+        let mut output = config.output.clone();
+        // The sink only counts.
+        output.mode = OutputMode::Raw;
+        output.color = ColorMode::Never;
+        routes.push(Route {
+            name: "stats".to_string(),
+            output,
+            filter: Default::default(),
+            sink: Box::new(crate::merge::sink::stats::StatsSink::new(
+                stats.clone(),
+                opts.only,
+            )),
+        });
+        let only = opts.only;
+        let task = tokio::spawn(crate::stream::stats::report(
+            stats.clone(),
+            opts,
+            stats_stop.clone(),
+        ));
+        (task, only)
+    });
+
     let (cmd_tx, mut cmd_rx) = mpsc::channel::<PodCommand>(128);
 
-    let (log_tx, log_rx) = mpsc::channel(config.runtime.buffer);
+    let (log_tx, log_rx) =
+        crate::stream::channel::log_channel(config.runtime.buffer, config.runtime.overflow);
 
     let watcher_handle = if config.dev_mode {
-        crate::dev::pods::spawn_dev_pods(config.namespace.clone(), cmd_tx)
+        crate::dev::pods::spawn_dev_pods(
+            namespace.clone(),
+            config.kube.clone(),
+            config.enrich.clone(),
+            config.dev.exit_code,
+            cmd_tx,
+        )
     } else {
-        let client = crate::kube::client::make_client().await?;
+        let client = crate::kube::client::make_client(
+            config.kube.context.as_deref(),
+            config.kube.kubeconfig.as_deref(),
+        )
+        .await?;
         crate::podwatch::watcher::spawn_pod_watcher(
             client,
-            config.namespace.clone(),
-            config.selector.clone(),
-            config.kube.containers.clone(),
+            namespace.clone(),
+            selector,
+            config.kube.clone(),
+            config.enrich.clone(),
+            status.clone(),
             cmd_tx,
         )
     };
 
-    let output_cfg = config.output.clone();
-
     let backend = if config.dev_mode {
         crate::stream::supervisor::StreamBackend::Dev {
             rate_ms: config.dev.rate_ms,
@@ -52,29 +135,69 @@ pub async fn run(config: Config) -> AppResult<()> {
         }
     } else {
         crate::stream::supervisor::StreamBackend::Kube {
-            client: crate::kube::client::make_client().await?,
-            opts: config.kube.clone(),
+            client: crate::kube::client::make_client(
+                config.kube.context.as_deref(),
+                config.kube.kubeconfig.as_deref(),
+            )
+            .await?,
+            opts: std::sync::Arc::new(config.kube.clone()),
         }
     };
 
+    let events_task = if !config.events {
+        None
+    } else if config.dev_mode {
+        Some(crate::dev::events::spawn_dev_events(
+            namespace.clone(),
+            config.kube.follow,
+            log_tx.clone(),
+        ))
+    } else {
+        let client = crate::kube::client::make_client(
+            config.kube.context.as_deref(),
+            config.kube.kubeconfig.as_deref(),
+        )
+        .await?;
+        Some(crate::podwatch::events::spawn_event_watcher(
+            client,
+            namespace.clone(),
+            config.kube.clone(),
+            status.subscribe_pods(),
+            log_tx.clone(),
+            shutdown_token.clone(),
+        ))
+    };
+
     let (fatal_tx, _fatal_rx) = mpsc::channel(1);
+    let streams = status.subscribe();
 
     let mut supervisor = crate::stream::supervisor::StreamSupervisor::new(
         log_tx,
         fatal_tx,
         backend,
+        config.limits.clone(),
+        status,
         shutdown_token.clone(),
     );
 
+    let follow = config.kube.follow;
     let cmd_loop_shutdown = shutdown_token.clone();
+    // Every command the pod source sent has been applied.
+    let commands_done = CancellationToken::new();
+    let supervisor_done = commands_done.clone();
     let supervisor_task = tokio::spawn(async move {
-        while let Some(cmd) = cmd_rx.recv().await {
-            if cmd_loop_shutdown.is_cancelled() {
-                break;
-            }
-            supervisor.handle_command(cmd);
+        loop {
+            let cmd = tokio::select! {
+                cmd = cmd_rx.recv() => cmd,
+                _ = cmd_loop_shutdown.cancelled() => None,
+            };
+            let Some(cmd) = cmd else { break };
+            supervisor.handle_command(cmd).await;
         }
-        supervisor
+        supervisor_done.cancel();
+        // Without --follow no more pods are coming: dropping the supervisor's
+        // sender lets the merger finish once the running streams do.
+        follow.then_some(supervisor)
     });
 
     #[cfg(unix)]
@@ -88,42 +211,137 @@ pub async fn run(config: Config) -> AppResult<()> {
     #[cfg(not(unix))]
     let sigterm_fut = async { std::future::pending::<()>().await };
 
+    let timeout = config.stop.timeout;
+    let timeout_fut = async move {
+        match timeout {
+            Some(d) => tokio::time::sleep(d).await,
+            None => std::future::pending::<()>().await,
+        }
+    };
+
+    let mut streams = streams;
+    let watcher_done = async move {
+        let join = watcher_handle.await;
+        if !follow && matches!(join, Ok(Ok(None))) {
+            // Without --follow the streams decide when we're done.
+            std::future::pending::<()>().await;
+        }
+        if matches!(join, Ok(Ok(Some(_)))) {
+            // The pods are done; let their last lines through first.
+            commands_done.cancelled().await;
+            let _ = streams
+                .wait_for(|t| t.values().all(|s| matches!(s, StreamState::Failed(_))))
+                .await;
+        }
+        join
+    };
+
     let monitor_task = tokio::spawn(async move {
-        tokio::select! {
-            _ = tokio::signal::ctrl_c() => {
-                monitor_shutdown.cancel();
-                tracing::info!(reason="CtrlC", "shutdown requested");
-            }
-            _ = sigterm_fut => {
-                monitor_shutdown.cancel();
-                tracing::info!(reason="Sigterm", "shutdown requested");
-            }
-            join = watcher_handle => {
-                match join {
-                    Ok(Ok(())) => tracing::info!(reason="WatcherEnded", "shutdown requested"),
-                    Ok(Err(e)) => tracing::error!(error=%e, reason="WatcherError", "shutdown requested"),
-                    Err(e) => tracing::error!(error=%e, reason="WatcherTaskJoinError", "shutdown requested"),
+        let mut outcome = None;
+        let reason = tokio::select! {
+            _ = tokio::signal::ctrl_c() => ShutdownReason::CtrlC,
+            _ = sigterm_fut => ShutdownReason::Sigterm,
+            _ = timeout_fut => ShutdownReason::Timeout,
+            join = watcher_done => match join {
+                Ok(Ok(Some(o))) => {
+                    outcome = Some(o);
+                    ShutdownReason::Completed
                 }
-                monitor_shutdown.cancel();
-            }
-        }
+                Ok(Ok(None)) => ShutdownReason::WatcherEnded,
+                Ok(Err(e)) => {
+                    tracing::error!(error=%e, "pod watch failed");
+                    ShutdownReason::WatcherError
+                }
+                Err(e) => {
+                    tracing::error!(error=%e, "pod watch task failed");
+                    ShutdownReason::WatcherError
+                }
+            },
+            // The output ended the run.
+            _ = monitor_shutdown.cancelled() => return (None, None),
+        };
+        tracing::info!(?reason, "shutdown requested");
+        monitor_shutdown.cancel();
+        (Some(reason), outcome)
     });
 
-    let merger_res = crate::merge::output::run_merger(log_rx, output_cfg).await;
+    let merger_res = crate::merge::output::run_merger(
+        log_rx,
+        routes,
+        config
+            .redact
+            .as_ref()
+            .map(crate::merge::redact::Redactor::new),
+        config
+            .dedupe
+            .clone()
+            .map(crate::merge::dedupe::Deduper::new),
+        crate::merge::stop::StopAt::new(&config.stop),
+        crate::merge::output::flush_idle(),
+        shutdown_token.clone(),
+    )
+    .await;
+    if let Ok(Some(reason)) = &merger_res {
+        tracing::info!(?reason, "shutdown requested");
+    }
+
+    // The view stays up after the last line until the user quits.
+    let merger_res = match tui_task {
+        Some(task) => match task.await {
+            Ok(tui_res) => merger_res.and_then(|stopped| tui_res.map(|()| stopped)),
+            Err(e) => Err(std::io::Error::other(e)),
+        },
+        None => merger_res,
+    };
+
+    // Everything is written; the last table counts all of it.
+    if let Some((task, only)) = stats_task {
+        stats_stop.cancel();
+        if let Ok(mut table) = task.await {
+            crate::stream::stats::print(&mut table, &stats, only);
+        }
+    }
 
     shutdown_token.cancel();
+    if let Some(task) = events_task {
+        task.abort();
+    }
 
     let mut supervisor = match supervisor_task.await {
-        Ok(s) => s,
+        Ok(Some(s)) => s,
+        Ok(None) => {
+            monitor_task.abort();
+            return Ok(merger_res?
+                .unwrap_or(ShutdownReason::WatcherEnded)
+                .unmatched(until_match));
+        }
         Err(e) => {
             tracing::error!(error=%e, "supervisor task failed");
             let _ = monitor_task.await;
-            return Ok(merger_res?);
+            return Ok(merger_res?
+                .unwrap_or(ShutdownReason::WatcherEnded)
+                .unmatched(until_match));
         }
     };
     supervisor.shutdown_all();
 
-    let _ = monitor_task.await;
+    let (reason, outcome) = monitor_task.await.unwrap_or((None, None));
+    let stopped = merger_res?;
 
-    Ok(merger_res?)
+    if let Some(outcome) = outcome {
+        if config.summary {
+            eprint!("{outcome}");
+        }
+        let failed = outcome.failed();
+        if failed > 0 {
+            return Err(AppError::Other(format!(
+                "{failed} of {} containers failed",
+                outcome.containers.len()
+            )));
+        }
+    }
+    Ok(stopped
+        .or(reason)
+        .unwrap_or(ShutdownReason::WatcherEnded)
+        .unmatched(until_match))
 }
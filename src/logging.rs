@@ -1,9 +1,31 @@
 use std::io;
 
-pub fn init() {
-    tracing_subscriber::fmt()
-        .with_writer(io::stderr)
+use tracing_subscriber::EnvFilter;
+
+/// Installs the stderr logger. `-v` (kubectl-style levels) overrides
+/// `RUST_LOG`. `quiet` discards everything, for when the TUI owns the
+/// terminal.
+pub fn init(verbosity: Option<u8>, quiet: bool) {
+    let builder = tracing_subscriber::fmt()
+        .with_writer(move || -> Box<dyn io::Write> {
+            if quiet {
+                Box::new(io::sink())
+            } else {
+                Box::new(io::stderr())
+            }
+        })
         .with_target(false)
-        .with_level(true)
-        .init();
+        .with_level(true);
+
+    match verbosity {
+        Some(v) => {
+            let level = match v {
+                0..=2 => "info",
+                3..=5 => "debug",
+                _ => "trace",
+            };
+            builder.with_env_filter(EnvFilter::new(level)).init();
+        }
+        None => builder.init(),
+    }
 }
@@ -1,14 +1,62 @@
-use clap::Parser;
+use std::process::ExitCode;
 
-use kpl::cli::Cli;
+use kpl::cli::Command;
 use kpl::config::Config;
 
+fn main() -> ExitCode {
+    // Runs before the tokio runtime: completers start their own to reach
+    // the API server.
+    kpl::complete::handle_request();
+
+    tail()
+}
+
 #[tokio::main]
-async fn main() -> kpl::errors::AppResult<()> {
-    kpl::logging::init();
+async fn tail() -> ExitCode {
+    let mut cli = match kpl::settings::parse_cli() {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+
+    kpl::logging::init(cli.verbosity, cli.tui);
+
+    let command = cli.command.take();
+    if let Some(Command::Completions { shell }) = command {
+        return match kpl::complete::write_script(shell, &mut std::io::stdout()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {e}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    // Bad flags and settings exit 2 like clap's own usage errors.
+    let config = match Config::try_from(cli) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
 
-    let cli = Cli::parse();
-    let config = Config::try_from(cli).expect("infallible config conversion failed");
+    let res = match command {
+        Some(Command::Pods) => kpl::podwatch::list::print_pods(&config)
+            .await
+            .map(|()| ExitCode::SUCCESS),
+        _ => kpl::run(config)
+            .await
+            .map(|reason| ExitCode::from(reason.exit_code())),
+    };
 
-    kpl::run(config).await
+    match res {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
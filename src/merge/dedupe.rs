@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+use tokio::time::Instant;
+
+use crate::config::DedupeOpts;
+use crate::types::{EventKind, LogEvent};
+
+/// Collapses consecutive identical lines per stream. The first line of a
+/// run goes out straight away; the repeats are held back and reported as
+/// one "(repeated N times)" line when the run breaks, times out or the tail
+/// ends.
+pub struct Deduper {
+    opts: DedupeOpts,
+    runs: HashMap<(String, String, String), Run>,
+}
+
+struct Run {
+    key: String,
+    last: LogEvent,
+    repeats: u64,
+    started: Instant,
+    /// When the stream's last line arrived
+    seen: Instant,
+}
+
+impl Deduper {
+    pub fn new(opts: DedupeOpts) -> Self {
+        Self {
+            opts,
+            runs: HashMap::new(),
+        }
+    }
+
+    pub fn push(&mut self, ev: LogEvent, out: &mut Vec<LogEvent>) {
+        if !ev.kind.is_log() {
+            out.push(ev);
+            return;
+        }
+
+        let key = if self.opts.normalize {
+            normalize(&ev.message)
+        } else {
+            ev.message.clone()
+        };
+        let stream = (ev.namespace.clone(), ev.pod.clone(), ev.container.clone());
+
+        if let Some(run) = self.runs.get_mut(&stream) {
+            run.seen = Instant::now();
+            if run.key == key {
+                if run.repeats == 0 {
+                    run.started = run.seen;
+                }
+                run.repeats += 1;
+                run.last = ev;
+                return;
+            }
+            if let Some(summary) = run.summary() {
+                out.push(summary);
+            }
+        }
+
+        out.push(ev.clone());
+        self.runs.insert(
+            stream,
+            Run {
+                key,
+                last: ev,
+                repeats: 0,
+                started: Instant::now(),
+                seen: Instant::now(),
+            },
+        );
+    }
+
+    /// Earliest time a held-back run has to be reported, or a quiet stream
+    /// forgotten.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.runs
+            .values()
+            .map(|r| r.deadline(self.opts.timeout))
+            .min()
+    }
+
+    /// Reports runs that timed out and forgets streams that stayed quiet for
+    /// the timeout with nothing held back, so pods that come and go don't
+    /// pile up. A line after that starts a new run.
+    pub fn expire(&mut self, out: &mut Vec<LogEvent>) {
+        let now = Instant::now();
+        let timeout = self.opts.timeout;
+        self.runs.retain(|_, run| {
+            if run.repeats > 0 && run.started + timeout <= now {
+                out.extend(run.summary());
+            }
+            run.repeats > 0 || run.seen + timeout > now
+        });
+    }
+
+    pub fn finish(&mut self, out: &mut Vec<LogEvent>) {
+        for (_, mut run) in self.runs.drain() {
+            out.extend(run.summary());
+        }
+    }
+}
+
+impl Run {
+    fn deadline(&self, timeout: Duration) -> Instant {
+        if self.repeats > 0 {
+            self.started + timeout
+        } else {
+            self.seen + timeout
+        }
+    }
+
+    /// Reports and resets the held-back repeats; the next identical line
+    /// starts a new visible run.
+    fn summary(&mut self) -> Option<LogEvent> {
+        if self.repeats == 0 {
+            return None;
+        }
+
+        let count = std::mem::take(&mut self.repeats);
+        self.key.clear();
+
+        Some(LogEvent {
+            message: format!("{} (repeated {count} times)", self.last.message),
+            kind: EventKind::Repeated { count },
+            ..self.last.clone()
+        })
+    }
+}
+
+/// Masks UUIDs, hex and decimal numbers so lines differing only in ids,
+/// counters or durations compare equal.
+fn normalize(s: &str) -> String {
+    static MASK: OnceLock<Regex> = OnceLock::new();
+    let re = MASK.get_or_init(|| {
+        Regex::new(
+            r"(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}|0x[0-9a-f]+|\b[0-9a-f]*\d[0-9a-f]*\b|\d+",
+        )
+        .expect("static regex")
+    });
+    re.replace_all(s, "#").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deduper(timeout: Duration, normalize: bool) -> Deduper {
+        Deduper::new(DedupeOpts { timeout, normalize })
+    }
+
+    fn line(pod: &str, message: &str) -> LogEvent {
+        LogEvent {
+            ts: time::OffsetDateTime::now_utc(),
+            namespace: "shop".to_string(),
+            pod: pod.to_string(),
+            container: "app".to_string(),
+            message: message.to_string(),
+            meta: None,
+            kind: EventKind::Log,
+        }
+    }
+
+    #[test]
+    fn normalize_masks_ids_hex_and_numbers() {
+        assert_eq!(
+            normalize("req 3f2a9c1e-0b4d-4e8f-9a6b-1c2d3e4f5a6b took 12ms at 0xDEADbeef"),
+            "req # took #ms at #"
+        );
+        assert_eq!(
+            normalize("retry 7 of 10, sha abc123f"),
+            "retry # of #, sha #"
+        );
+        // Words made only of hex letters are left alone.
+        assert_eq!(normalize("cafe added a bed"), "cafe added a bed");
+    }
+
+    #[tokio::test]
+    async fn normalized_lines_collapse_into_one_run() {
+        let mut dedupe = deduper(Duration::from_secs(60), true);
+        let mut out = Vec::new();
+        for ms in [12, 15, 9] {
+            dedupe.push(line("api-0", &format!("GET /health took {ms}ms")), &mut out);
+        }
+        dedupe.finish(&mut out);
+
+        let messages: Vec<&str> = out.iter().map(|e| e.message.as_str()).collect();
+        // The summary quotes the last line of the run.
+        assert_eq!(
+            messages,
+            [
+                "GET /health took 12ms",
+                "GET /health took 9ms (repeated 2 times)"
+            ]
+        );
+
+        // Without --dedupe-normalize they are all different.
+        let mut dedupe = deduper(Duration::from_secs(60), false);
+        let mut out = Vec::new();
+        for ms in [12, 15] {
+            dedupe.push(line("api-0", &format!("GET /health took {ms}ms")), &mut out);
+        }
+        dedupe.finish(&mut out);
+        assert_eq!(out.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn finish_reports_every_held_back_run() {
+        let mut dedupe = deduper(Duration::from_secs(60), false);
+        let mut out = Vec::new();
+        for _ in 0..4 {
+            dedupe.push(line("api-0", "ping"), &mut out);
+        }
+        dedupe.push(line("api-1", "pong"), &mut out);
+        dedupe.push(line("api-1", "pong"), &mut out);
+        dedupe.push(line("api-2", "alone"), &mut out);
+        out.clear();
+
+        dedupe.finish(&mut out);
+        out.sort_by(|a, b| a.pod.cmp(&b.pod));
+        let summaries: Vec<(&str, &str, &EventKind)> = out
+            .iter()
+            .map(|e| (e.pod.as_str(), e.message.as_str(), &e.kind))
+            .collect();
+        assert_eq!(
+            summaries,
+            [
+                (
+                    "api-0",
+                    "ping (repeated 3 times)",
+                    &EventKind::Repeated { count: 3 }
+                ),
+                (
+                    "api-1",
+                    "pong (repeated 1 times)",
+                    &EventKind::Repeated { count: 1 }
+                ),
+            ]
+        );
+        assert!(dedupe.runs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn idle_streams_are_forgotten_after_the_timeout() {
+        let timeout = Duration::from_millis(30);
+        let mut dedupe = deduper(timeout, false);
+        let mut out = Vec::new();
+        dedupe.push(line("api-0", "ping"), &mut out);
+        assert_eq!(dedupe.runs.len(), 1);
+
+        // Before the timeout the stream is kept and a repeat is held back.
+        dedupe.expire(&mut out);
+        assert_eq!(dedupe.runs.len(), 1);
+
+        tokio::time::sleep(timeout).await;
+        dedupe.expire(&mut out);
+        assert!(dedupe.runs.is_empty());
+        assert_eq!(dedupe.next_deadline(), None);
+
+        // So the same line after a quiet spell is shown again.
+        out.clear();
+        dedupe.push(line("api-0", "ping"), &mut out);
+        assert_eq!(out.len(), 1);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::merge::format::format_ts;
+use crate::types::LogEvent;
+
+const COLUMNS: [&str; 5] = ["ts", "namespace", "pod", "container", "message"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    Comma,
+    Tab,
+}
+
+impl Delimiter {
+    fn as_char(self) -> char {
+        match self {
+            Delimiter::Comma => ',',
+            Delimiter::Tab => '\t',
+        }
+    }
+}
+
+pub fn header(delim: Delimiter) -> String {
+    join(delim, COLUMNS.iter().copied())
+}
+
+pub fn format(ev: &LogEvent, delim: Delimiter) -> String {
+    let ts = format_ts(&ev.ts);
+
+    join(
+        delim,
+        [
+            ts.as_str(),
+            ev.namespace.as_str(),
+            ev.pod.as_str(),
+            ev.container.as_str(),
+            ev.message.as_str(),
+        ]
+        .into_iter(),
+    )
+}
+
+fn join<'a>(delim: Delimiter, fields: impl Iterator<Item = &'a str>) -> String {
+    let mut line = String::new();
+    for (i, f) in fields.enumerate() {
+        if i > 0 {
+            line.push(delim.as_char());
+        }
+        match delim {
+            Delimiter::Comma => push_csv(&mut line, f),
+            Delimiter::Tab => push_tsv(&mut line, f),
+        }
+    }
+    line
+}
+
+/// RFC 4180: quote fields containing separators, quotes or line breaks.
+fn push_csv(line: &mut String, f: &str) {
+    if !f.contains([',', '"', '\n', '\r']) {
+        line.push_str(f);
+        return;
+    }
+
+    line.push('"');
+    line.push_str(&f.replace('"', "\"\""));
+    line.push('"');
+}
+
+/// TSV has no quoting, so tabs and line breaks are backslash-escaped.
+fn push_tsv(line: &mut String, f: &str) {
+    for c in f.chars() {
+        match c {
+            '\t' => line.push_str("\\t"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\\' => line.push_str("\\\\"),
+            c => line.push(c),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::merge::format::format_ts;
+use crate::types::{split_image, Change, ColorBy, ColorMode, EventKind, LogEvent, OutputConfig};
+use owo_colors::OwoColorize;
+use std::hash::{Hash, Hasher};
+use std::io::IsTerminal;
+
+const LABEL_COL_WIDTH: usize = 36;
+
+pub fn format(ev: &LogEvent, out: &OutputConfig) -> String {
+    let ts = format_ts(&ev.ts);
+
+    // Events about the whole pod have no container.
+    let mut label_plain = if ev.container.is_empty() {
+        ev.pod.clone()
+    } else {
+        format!("{}/{}", ev.pod, ev.container)
+    };
+    if out.label_meta {
+        push_meta(&mut label_plain, ev);
+    }
+
+    let label_padded = pad_label(&label_plain, LABEL_COL_WIDTH);
+
+    let label_final = if should_color(out) {
+        colorize_label(label_padded, out.color_by, &ev.pod, &ev.container)
+    } else {
+        label_padded
+    };
+
+    if let EventKind::Event(e) = &ev.kind {
+        let text = format!("{} {}: {}", e.event_type, e.reason, ev.message);
+        let text = if e.count > 1 {
+            format!("{text} (x{})", e.count)
+        } else {
+            text
+        };
+        if !should_color(out) {
+            format!("{ts} {label_final} ⚑ {text}")
+        } else if e.is_warning() {
+            format!("{ts} {label_final} ⚑ {}", text.yellow())
+        } else {
+            format!("{ts} {label_final} ⚑ {}", text.cyan())
+        }
+    } else if let EventKind::Lifecycle(l) = &ev.kind {
+        // The message starts with its own +/-/↻ marker.
+        if !should_color(out) {
+            format!("{ts} {label_final} {}", ev.message)
+        } else {
+            let msg = match l.change {
+                Change::Started | Change::Ready => ev.message.green().to_string(),
+                Change::Restarted => ev.message.yellow().to_string(),
+                Change::Terminated | Change::Deleted => ev.message.red().to_string(),
+            };
+            format!("{ts} {label_final} {}", msg.bold())
+        }
+    } else if !ev.kind.is_notice() {
+        format!("{ts} {label_final} │ {}", ev.message)
+    } else if should_color(out) {
+        format!("{ts} {label_final} ! {}", ev.message.dimmed())
+    } else {
+        format!("{ts} {label_final} ! {}", ev.message)
+    }
+}
+
+fn push_meta(label: &mut String, ev: &LogEvent) {
+    let Some(meta) = &ev.meta else {
+        return;
+    };
+
+    if let Some(tag) = meta.image(&ev.container).and_then(|i| split_image(i).1) {
+        label.push('@');
+        label.push_str(tag);
+    }
+    if let Some(node) = &meta.node {
+        label.push(' ');
+        label.push_str(node);
+    }
+    for (k, v) in &meta.labels {
+        label.push_str(&format!(" {k}={v}"));
+    }
+}
+
+fn pad_label(s: &str, width: usize) -> String {
+    if s.len() >= width {
+        s.to_string()
+    } else {
+        format!("{s:<width$}", width = width)
+    }
+}
+
+fn should_color(out: &OutputConfig) -> bool {
+    match out.color {
+        ColorMode::Never => false,
+        ColorMode::Always => true,
+        ColorMode::Auto => std::io::stdout().is_terminal(),
+    }
+}
+
+fn colorize_label(padded: String, color_by: ColorBy, pod: &str, container: &str) -> String {
+    let key = match color_by {
+        ColorBy::Pod => pod,
+        ColorBy::Container => container,
+    };
+
+    let idx = stable_color_index(key);
+
+    match idx {
+        0 => padded.bright_blue().to_string(),
+        1 => padded.bright_green().to_string(),
+        2 => padded.bright_magenta().to_string(),
+        3 => padded.bright_cyan().to_string(),
+        4 => padded.bright_yellow().to_string(),
+        5 => padded.bright_red().to_string(),
+        6 => padded.blue().to_string(),
+        7 => padded.green().to_string(),
+        8 => padded.magenta().to_string(),
+        9 => padded.cyan().to_string(),
+        _ => padded.yellow().to_string(),
+    }
+}
+
+pub(crate) fn stable_color_index(s: &str) -> usize {
+    let mut h = std::collections::hash_map::DefaultHasher::new();
+    s.hash(&mut h);
+    (h.finish() as usize) % 11
+}
@@ -0,0 +1,33 @@
+use crate::merge::format::format_ts;
+use crate::types::LogEvent;
+
+pub fn format(ev: &LogEvent) -> String {
+    let ts = format_ts(&ev.ts);
+
+    let mut obj = serde_json::json!({
+        "ts": ts,
+        "namespace": ev.namespace,
+        "pod": ev.pod,
+        "container": ev.container,
+        "message": ev.message,
+    });
+
+    if !ev.kind.is_log() {
+        if let serde_json::Value::Object(kind) = serde_json::to_value(&ev.kind).unwrap_or_default()
+        {
+            for (k, v) in kind {
+                obj[k] = v;
+            }
+        }
+    }
+
+    if let Some(meta) = &ev.meta {
+        let mut m = serde_json::to_value(meta.as_ref()).unwrap_or_default();
+        if let Some(image) = meta.image(&ev.container) {
+            m["image"] = image.into();
+        }
+        obj["meta"] = m;
+    }
+
+    obj.to_string()
+}
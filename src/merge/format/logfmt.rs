@@ -0,0 +1,75 @@
+use crate::merge::format::format_ts;
+use crate::types::{EventKind, LogEvent};
+
+pub fn format(ev: &LogEvent) -> String {
+    let mut pairs: Vec<(&str, String)> = vec![
+        ("ts", format_ts(&ev.ts)),
+        ("namespace", ev.namespace.clone()),
+        ("pod", ev.pod.clone()),
+        ("container", ev.container.clone()),
+    ];
+
+    if !ev.kind.is_log() {
+        pairs.push(("kind", ev.kind.name().to_string()));
+    }
+    if let Some(count) = ev.kind.count() {
+        pairs.push(("count", count.to_string()));
+    }
+    if let EventKind::Event(e) = &ev.kind {
+        pairs.push(("event_type", e.event_type.clone()));
+        pairs.push(("reason", e.reason.clone()));
+        pairs.push(("count", e.count.to_string()));
+        if let Some(reporter) = &e.reporter {
+            pairs.push(("reporter", reporter.clone()));
+        }
+    }
+    if let EventKind::Lifecycle(l) = &ev.kind {
+        pairs.push(("change", l.change.name().to_string()));
+        if let Some(reason) = &l.reason {
+            pairs.push(("reason", reason.clone()));
+        }
+        if let Some(code) = l.exit_code {
+            pairs.push(("exit_code", code.to_string()));
+        }
+        if let Some(restarts) = l.restarts {
+            pairs.push(("restarts", restarts.to_string()));
+        }
+    }
+
+    pairs.push(("msg", ev.message.clone()));
+
+    let mut line = String::new();
+    for (i, (k, v)) in pairs.iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        line.push_str(k);
+        line.push('=');
+        push_value(&mut line, v);
+    }
+    line
+}
+
+fn push_value(line: &mut String, v: &str) {
+    let needs_quotes = v.is_empty()
+        || v.chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+
+    if !needs_quotes {
+        line.push_str(v);
+        return;
+    }
+
+    line.push('"');
+    for c in v.chars() {
+        match c {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            c => line.push(c),
+        }
+    }
+    line.push('"');
+}
@@ -0,0 +1,35 @@
+pub mod delimited;
+pub mod human;
+pub mod json;
+pub mod logfmt;
+pub mod otlp;
+pub mod raw;
+
+use crate::types::{LogEvent, OutputConfig, OutputMode};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+pub fn format_event(ev: &LogEvent, out: &OutputConfig) -> String {
+    match out.mode {
+        OutputMode::Human => human::format(ev, out),
+        OutputMode::Json => json::format(ev),
+        OutputMode::Logfmt => logfmt::format(ev),
+        OutputMode::Csv => delimited::format(ev, delimited::Delimiter::Comma),
+        OutputMode::Tsv => delimited::format(ev, delimited::Delimiter::Tab),
+        OutputMode::OtlpJson => otlp::format(ev),
+        OutputMode::Raw => raw::format(ev, out),
+    }
+}
+
+/// Line written once before the first event, for formats that need one.
+pub fn format_header(out: &OutputConfig) -> Option<String> {
+    match out.mode {
+        OutputMode::Csv => Some(delimited::header(delimited::Delimiter::Comma)),
+        OutputMode::Tsv => Some(delimited::header(delimited::Delimiter::Tab)),
+        _ => None,
+    }
+}
+
+pub(crate) fn format_ts(ts: &OffsetDateTime) -> String {
+    ts.format(&Rfc3339).unwrap_or_else(|_| ts.to_string())
+}
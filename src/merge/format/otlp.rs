@@ -0,0 +1,131 @@
+use crate::types::{split_image, EventKind, LogEvent};
+
+const SCOPE_NAME: &str = "kpl";
+
+/// One OTLP/JSON `ExportLogsServiceRequest` per line, as read by the
+/// collector's `otlpjsonfile` receiver.
+pub fn format(ev: &LogEvent) -> String {
+    let ts_nanos = ev.ts.unix_timestamp_nanos().to_string();
+
+    let obj = serde_json::json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": resource_attrs(ev),
+            },
+            "scopeLogs": [{
+                "scope": {
+                    "name": SCOPE_NAME,
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "logRecords": [log_record(ev, &ts_nanos)],
+            }],
+        }],
+    });
+
+    obj.to_string()
+}
+
+fn log_record(ev: &LogEvent, ts_nanos: &str) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "timeUnixNano": ts_nanos,
+        "observedTimeUnixNano": ts_nanos,
+        "body": { "stringValue": ev.message },
+    });
+
+    if !ev.kind.is_log() {
+        let mut attrs = vec![string_attr("kpl.event.kind", ev.kind.name())];
+        if let Some(count) = ev.kind.count() {
+            attrs.push(serde_json::json!({
+                "key": "kpl.event.count",
+                "value": { "intValue": count.to_string() },
+            }));
+        }
+        if let EventKind::Event(e) = &ev.kind {
+            attrs.push(string_attr("k8s.event.type", &e.event_type));
+            attrs.push(string_attr("k8s.event.reason", &e.reason));
+            attrs.push(serde_json::json!({
+                "key": "k8s.event.count",
+                "value": { "intValue": e.count.to_string() },
+            }));
+            record["severityText"] = if e.is_warning() { "WARN" } else { "INFO" }.into();
+        }
+        if let EventKind::Lifecycle(l) = &ev.kind {
+            attrs.push(string_attr("kpl.lifecycle.change", l.change.name()));
+            if let Some(reason) = &l.reason {
+                attrs.push(string_attr("kpl.lifecycle.reason", reason));
+            }
+            if let Some(code) = l.exit_code {
+                attrs.push(serde_json::json!({
+                    "key": "kpl.lifecycle.exit_code",
+                    "value": { "intValue": code.to_string() },
+                }));
+            }
+        }
+        record["attributes"] = attrs.into();
+    }
+
+    record
+}
+
+fn resource_attrs(ev: &LogEvent) -> Vec<serde_json::Value> {
+    let mut attrs = vec![
+        string_attr("k8s.namespace.name", &ev.namespace),
+        string_attr("k8s.pod.name", &ev.pod),
+        string_attr("k8s.container.name", &ev.container),
+    ];
+
+    let Some(meta) = &ev.meta else {
+        return attrs;
+    };
+
+    let optional = [
+        ("k8s.node.name", meta.node.as_deref()),
+        ("k8s.pod.ip", meta.pod_ip.as_deref()),
+        ("k8s.replicaset.name", meta.replica_set.as_deref()),
+        ("k8s.deployment.name", meta.deployment.as_deref()),
+    ];
+    for (key, value) in optional {
+        if let Some(v) = value {
+            attrs.push(string_attr(key, v));
+        }
+    }
+
+    if let Some(image) = meta.image(&ev.container) {
+        let (name, tag) = split_image(image);
+        attrs.push(string_attr("container.image.name", name));
+        if let Some(tag) = tag {
+            attrs.push(string_attr("container.image.tag", tag));
+        }
+    }
+
+    for (k, v) in &meta.labels {
+        attrs.push(string_attr(&format!("k8s.pod.label.{k}"), v));
+    }
+    for (k, v) in &meta.annotations {
+        attrs.push(string_attr(&format!("k8s.pod.annotation.{k}"), v));
+    }
+
+    attrs
+}
+
+fn string_attr(key: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({
+        "key": key,
+        "value": { "stringValue": value },
+    })
+}
+
+/// Combines several formatted lines into one export request.
+pub fn merge_requests(lines: &[String]) -> String {
+    let resource_logs: Vec<serde_json::Value> = lines
+        .iter()
+        .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+        .filter_map(|mut v| match v["resourceLogs"].take() {
+            serde_json::Value::Array(items) => Some(items),
+            _ => None,
+        })
+        .flatten()
+        .collect();
+
+    serde_json::json!({ "resourceLogs": resource_logs }).to_string()
+}
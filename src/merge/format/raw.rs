@@ -0,0 +1,21 @@
+use crate::merge::format::format_ts;
+use crate::types::{LogEvent, OutputConfig};
+
+/// Bare message, exactly as read from the container, optionally led by
+/// kubectl's `--prefix` and `--timestamps` decorations.
+pub fn format(ev: &LogEvent, out: &OutputConfig) -> String {
+    if !out.prefix && !out.timestamps {
+        return ev.message.clone();
+    }
+
+    let mut line = String::new();
+    if out.prefix {
+        line.push_str(&format!("[pod/{}/{}] ", ev.pod, ev.container));
+    }
+    if out.timestamps {
+        line.push_str(&format_ts(&ev.ts));
+        line.push(' ');
+    }
+    line.push_str(&ev.message);
+    line
+}
@@ -1,2 +1,6 @@
+pub mod dedupe;
 pub mod format;
 pub mod output;
+pub mod redact;
+pub mod sink;
+pub mod stop;
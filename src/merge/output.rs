@@ -1,23 +1,196 @@
+use crate::merge::dedupe::Deduper;
 use crate::merge::format::format_event;
-use crate::types::{LogEvent, OutputConfig};
-use std::io::{self, Write};
-use tokio::sync::mpsc;
+use crate::merge::redact::Redactor;
+use crate::merge::sink::Route;
+use crate::merge::stop::StopAt;
+use crate::shutdown::ShutdownReason;
+use crate::stream::channel::LogRx;
+use crate::types::LogEvent;
+use std::io::{self, IsTerminal};
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
-pub async fn run_merger(mut rx: mpsc::Receiver<LogEvent>, output: OutputConfig) -> io::Result<()> {
-    while let Some(ev) = rx.recv().await {
-        let line = format_event(&ev, &output);
+/// Upper bound on events written between two checks of the channel.
+const MAX_BATCH: usize = 1024;
 
-        let mut out = io::stdout().lock();
+/// How long buffered output may wait before it is flushed, however busy the
+/// channel is. A terminal gets every batch as soon as it is written; pipes
+/// and files can afford to coalesce a little longer.
+pub fn flush_idle() -> Duration {
+    if io::stdout().is_terminal() {
+        Duration::ZERO
+    } else {
+        Duration::from_millis(50)
+    }
+}
+
+pub async fn run_merger(
+    mut rx: LogRx,
+    mut routes: Vec<Route>,
+    redact: Option<Redactor>,
+    mut dedupe: Option<Deduper>,
+    mut stop: Option<StopAt>,
+    flush_idle: Duration,
+    shutdown: CancellationToken,
+) -> io::Result<Option<ShutdownReason>> {
+    let mut batch: Vec<LogEvent> = Vec::with_capacity(MAX_BATCH);
+    let mut ready: Vec<LogEvent> = Vec::with_capacity(MAX_BATCH);
+    // When the oldest unflushed line is due out.
+    let mut flush_at: Option<Instant> = None;
+    let mut draining = false;
+    let mut stopped = None;
+
+    while has_output(&routes) {
+        if !draining {
+            let idle = async {
+                match flush_at {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending::<()>().await,
+                }
+            };
+            let dedupe_deadline = dedupe.as_ref().and_then(Deduper::next_deadline);
+            let dedupe_expired = async {
+                match dedupe_deadline {
+                    Some(at) => tokio::time::sleep_until(at).await,
+                    None => std::future::pending::<()>().await,
+                }
+            };
+
+            tokio::select! {
+                biased;
+                ev = rx.recv() => match ev {
+                    Some(ev) => batch.push(ev),
+                    None => break,
+                },
+                _ = shutdown.cancelled() => {
+                    // Write out whatever the streams already queued, then stop.
+                    rx.close();
+                    draining = true;
+                }
+                _ = idle => {
+                    flush_routes(&mut routes).await?;
+                    flush_at = None;
+                    continue;
+                }
+                _ = dedupe_expired => {
+                    if let Some(d) = dedupe.as_mut() {
+                        d.expire(&mut ready);
+                    }
+                }
+            }
+        }
+
+        while batch.len() < MAX_BATCH {
+            match rx.try_recv() {
+                Ok(ev) => batch.push(ev),
+                Err(_) => break,
+            }
+        }
+
+        if draining && batch.is_empty() {
+            break;
+        }
 
-        if let Err(e) = writeln!(out, "{line}") {
-            if e.kind() == io::ErrorKind::BrokenPipe {
-                return Ok(());
+        for mut ev in batch.drain(..) {
+            if let Some(r) = redact.as_ref() {
+                r.apply(&mut ev);
+            }
+            match dedupe.as_mut() {
+                Some(d) => d.push(ev, &mut ready),
+                None => ready.push(ev),
+            }
+        }
+        for ev in ready.drain(..) {
+            write_routes(&mut routes, &ev).await?;
+            flush_at.get_or_insert_with(|| Instant::now() + flush_idle);
+            stopped = stop.as_mut().and_then(|s| s.after(&ev));
+            if stopped.is_some() {
+                break;
             }
-            return Err(e);
         }
+        if stopped.is_some() {
+            break;
+        }
+        // A channel that never goes quiet would starve the idle branch.
+        if flush_at.is_some_and(|at| at <= Instant::now()) {
+            flush_routes(&mut routes).await?;
+            flush_at = None;
+        }
+    }
 
-        let _ = out.flush();
+    // Held-back repeats would go past the line the run stopped at.
+    if let Some(d) = dedupe.as_mut().filter(|_| stopped.is_none()) {
+        d.finish(&mut ready);
+        for ev in ready.drain(..) {
+            write_routes(&mut routes, &ev).await?;
+        }
+    }
+
+    let mut first_err = None;
+    for route in routes {
+        if let Err(e) = route.sink.close().await {
+            tracing::error!(sink = %route.name, error = %e, "failed to close sink");
+            first_err.get_or_insert(e);
+        }
     }
 
+    match first_err {
+        Some(e) => Err(e),
+        None => Ok(stopped),
+    }
+}
+
+async fn write_routes(routes: &mut Vec<Route>, ev: &LogEvent) -> io::Result<()> {
+    let mut i = 0;
+    while i < routes.len() {
+        let route = &mut routes[i];
+        let res = if route.filter.matches(ev) {
+            let line = format_event(ev, &route.output);
+            route.sink.write(ev, &line).await
+        } else {
+            Ok(())
+        };
+
+        match res {
+            Ok(()) => i += 1,
+            Err(e) => drop_route(routes, i, e)?,
+        }
+    }
+    Ok(())
+}
+
+async fn flush_routes(routes: &mut Vec<Route>) -> io::Result<()> {
+    let mut i = 0;
+    while i < routes.len() {
+        match routes[i].sink.flush().await {
+            Ok(()) => i += 1,
+            Err(e) => drop_route(routes, i, e)?,
+        }
+    }
     Ok(())
 }
+
+/// A failing sink is dropped so the others keep receiving events. The error
+/// only ends the run when no output is left (a closed pipe, e.g. `kpl | head`,
+/// is never an error).
+fn drop_route(routes: &mut Vec<Route>, i: usize, e: io::Error) -> io::Result<()> {
+    let route = routes.remove(i);
+
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        tracing::debug!(sink = %route.name, "sink closed");
+        return Ok(());
+    }
+
+    if !has_output(routes) {
+        return Err(e);
+    }
+
+    tracing::error!(sink = %route.name, error = %e, "sink failed; continuing without it");
+    Ok(())
+}
+
+/// Hooks and counters alone don't keep the merger running.
+fn has_output(routes: &[Route]) -> bool {
+    routes.iter().any(|r| r.sink.is_output())
+}
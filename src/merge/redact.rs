@@ -0,0 +1,174 @@
+use std::net::Ipv6Addr;
+
+use regex::Regex;
+
+use crate::config::RedactOpts;
+use crate::types::LogEvent;
+
+/// Masks secrets and personal data in messages before they reach any sink.
+///
+/// Each match becomes `[REDACTED:kind]`. When a rule has named capture
+/// groups only those groups are masked, under the group's name, so
+/// `password=(?P<password>\S+)` keeps the `password=` part readable.
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    kind: &'static str,
+    re: Regex,
+    /// Extra check on the masked text, for patterns a regex can't pin down
+    valid: Check,
+}
+
+type Check = fn(&str) -> bool;
+
+/// Built-in detectors, applied in this order.
+const BUILTIN: &[(&str, &str, Check)] = &[
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]+\.eyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*",
+        any,
+    ),
+    (
+        "bearer",
+        // Real tokens are long; this keeps "the bearer of" readable.
+        r"(?i)\bbearer\s+(?P<bearer>[A-Za-z0-9\-._~+/]{16,}=*)",
+        any,
+    ),
+    ("aws", r"\b(?P<aws>(?:AKIA|ASIA)[0-9A-Z]{16})\b", any),
+    (
+        "aws",
+        r#"(?i)aws_secret_access_key["']?\s*[=:]\s*["']?(?P<aws>[A-Za-z0-9/+=]{40})"#,
+        any,
+    ),
+    (
+        "email",
+        r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b",
+        any,
+    ),
+    ("card", r"\b(?:\d[ -]?){12,18}\d\b", luhn),
+    (
+        "ip",
+        r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b",
+        any,
+    ),
+    (
+        "ip",
+        r"(?:^|[^0-9A-Za-z_:])(?P<ip>(?:[0-9A-Fa-f]{1,4})?(?::[0-9A-Fa-f]{0,4}){2,7})(?:$|[^0-9A-Za-z_:])",
+        ipv6,
+    ),
+];
+
+impl Redactor {
+    pub fn new(opts: &RedactOpts) -> Self {
+        let mut rules = Vec::new();
+
+        if opts.builtin {
+            rules.extend(BUILTIN.iter().map(|&(kind, pattern, valid)| Rule {
+                kind,
+                re: Regex::new(pattern).expect("static regex"),
+                valid,
+            }));
+        }
+        rules.extend(opts.rules.iter().map(|re| Rule {
+            kind: "custom",
+            re: re.clone(),
+            valid: any,
+        }));
+
+        Self { rules }
+    }
+
+    pub fn apply(&self, ev: &mut LogEvent) {
+        for rule in &self.rules {
+            if let Some(masked) = rule.mask(&ev.message) {
+                ev.message = masked;
+            }
+        }
+    }
+}
+
+impl Rule {
+    /// Returns the masked text, or `None` if nothing matched.
+    fn mask(&self, s: &str) -> Option<String> {
+        let mut spans: Vec<(usize, usize, &str)> = Vec::new();
+
+        for caps in self.re.captures_iter(s) {
+            let named: Vec<_> = self
+                .re
+                .capture_names()
+                .flatten()
+                .filter_map(|name| caps.name(name).map(|m| (m, name)))
+                .collect();
+
+            if named.is_empty() {
+                let m = caps.get(0).expect("group 0 always matches");
+                spans.push((m.start(), m.end(), self.kind));
+            } else {
+                spans.extend(
+                    named
+                        .into_iter()
+                        .map(|(m, name)| (m.start(), m.end(), name)),
+                );
+            }
+        }
+        spans.retain(|&(start, end, _)| end > start && (self.valid)(&s[start..end]));
+
+        if spans.is_empty() {
+            return None;
+        }
+
+        let mut out = String::with_capacity(s.len());
+        let mut at = 0;
+        for (start, end, kind) in spans {
+            if start < at {
+                continue;
+            }
+            out.push_str(&s[at..start]);
+            out.push_str("[REDACTED:");
+            out.push_str(kind);
+            out.push(']');
+            at = end;
+        }
+        out.push_str(&s[at..]);
+        Some(out)
+    }
+}
+
+fn any(_: &str) -> bool {
+    true
+}
+
+/// Card numbers must pass the Luhn check, which keeps ids and timestamps out.
+fn luhn(s: &str) -> bool {
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let d = d * 2;
+                if d > 9 {
+                    d - 9
+                } else {
+                    d
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// The IPv6 pattern is loose (it also matches times like `12:30:00` and
+/// paths like `a::b`), so only text that parses as an address counts.
+fn ipv6(s: &str) -> bool {
+    s.len() > 2 && s.parse::<Ipv6Addr>().is_ok()
+}
@@ -0,0 +1,39 @@
+use std::io;
+
+use futures::future::BoxFuture;
+use tokio::sync::mpsc;
+
+use crate::merge::sink::Sink;
+use crate::types::LogEvent;
+
+/// Hands events to another task (the TUI) instead of writing them out. The
+/// formatted line is ignored; the receiver renders events itself.
+pub struct ChannelSink {
+    tx: mpsc::Sender<LogEvent>,
+}
+
+impl ChannelSink {
+    pub fn new(tx: mpsc::Sender<LogEvent>) -> Self {
+        Self { tx }
+    }
+}
+
+impl Sink for ChannelSink {
+    fn write<'a>(&'a mut self, ev: &'a LogEvent, _line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            // A closed receiver means the user quit; treat it like `| head`.
+            self.tx
+                .send(ev.clone())
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+        })
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
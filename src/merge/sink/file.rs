@@ -0,0 +1,45 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use futures::future::BoxFuture;
+
+use crate::merge::sink::Sink;
+use crate::types::LogEvent;
+
+/// Appends the merged stream to a single file.
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn open(path: &Path, header: Option<String>) -> io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let f = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = f.metadata()?.len() == 0;
+        let mut writer = BufWriter::new(f);
+
+        if let (true, Some(header)) = (empty, header) {
+            writeln!(writer, "{header}")?;
+        }
+
+        Ok(Self { writer })
+    }
+}
+
+impl Sink for FileSink {
+    fn write<'a>(&'a mut self, _ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(std::future::ready(writeln!(self.writer, "{line}")))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(self.writer.flush()))
+    }
+
+    fn close(mut self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(std::future::ready(self.writer.flush()))
+    }
+}
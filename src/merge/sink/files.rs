@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use tokio::task::JoinSet;
+
+use crate::config::FileOutputOpts;
+use crate::merge::sink::Sink;
+use crate::types::{LogEvent, POD_LEVEL};
+
+/// Open files kept at once; past this the least recently written is closed
+/// and reopened (appending) if its stream writes again.
+const MAX_OPEN: usize = 128;
+
+/// Writes each stream to its own file under `--output-dir`.
+pub struct SplitFiles {
+    opts: FileOutputOpts,
+    header: Option<String>,
+    files: HashMap<PathBuf, OpenFile>,
+    compressing: JoinSet<io::Result<()>>,
+}
+
+struct OpenFile {
+    writer: BufWriter<File>,
+    bytes: u64,
+    opened: Instant,
+    written: Instant,
+}
+
+impl SplitFiles {
+    pub fn new(opts: FileOutputOpts, header: Option<String>) -> io::Result<Self> {
+        fs::create_dir_all(&opts.dir)?;
+        Ok(Self {
+            opts,
+            header,
+            files: HashMap::new(),
+            compressing: JoinSet::new(),
+        })
+    }
+
+    fn write_line(&mut self, ev: &LogEvent, line: &str) -> io::Result<()> {
+        let path = self.path_for(ev);
+        let len = line.len() as u64 + 1;
+
+        if let Some(file) = self.files.get(&path) {
+            if self.should_rotate(file, len) {
+                let file = self.files.remove(&path).expect("file present");
+                self.rotate(&path, file)?;
+            }
+        }
+
+        if !self.files.contains_key(&path) {
+            if self.files.len() >= MAX_OPEN {
+                self.close_oldest()?;
+            }
+            let f = open(&path, self.header.as_deref())?;
+            self.files.insert(path.clone(), f);
+        }
+        let file = self.files.get_mut(&path).expect("file present");
+
+        writeln!(file.writer, "{line}")?;
+        file.bytes += len;
+        file.written = Instant::now();
+
+        Ok(())
+    }
+
+    /// Flushes every open file, rotating those past `--rotate-interval` so
+    /// a quiet stream's file doesn't wait for its next line.
+    fn flush_all(&mut self) -> io::Result<()> {
+        let due: Vec<PathBuf> = self
+            .files
+            .iter()
+            .filter(|(_, file)| self.should_rotate(file, 0))
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in due {
+            let file = self.files.remove(&path).expect("file present");
+            self.rotate(&path, file)?;
+        }
+
+        for file in self.files.values_mut() {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Streams of pods that are gone stop writing; their files go first.
+    fn close_oldest(&mut self) -> io::Result<()> {
+        let oldest = self
+            .files
+            .iter()
+            .min_by_key(|(_, file)| file.written)
+            .map(|(path, _)| path.clone());
+        if let Some(mut file) = oldest.and_then(|path| self.files.remove(&path)) {
+            file.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn path_for(&self, ev: &LogEvent) -> PathBuf {
+        // Pod-level lines (events, lifecycle) have no container; an empty
+        // name would make a hidden `.log` file.
+        let container = match ev.container.as_str() {
+            "" => POD_LEVEL,
+            name => name,
+        };
+        let rel = self
+            .opts
+            .template
+            .replace("{namespace}", &sanitize(&ev.namespace))
+            .replace("{pod}", &sanitize(&ev.pod))
+            .replace("{container}", &sanitize(container));
+
+        // The template may add directories but must not escape --output-dir.
+        let rel: PathBuf = Path::new(&rel)
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect();
+
+        self.opts.dir.join(rel)
+    }
+
+    fn should_rotate(&self, file: &OpenFile, incoming: u64) -> bool {
+        let too_big = self
+            .opts
+            .rotate_bytes
+            .is_some_and(|max| file.bytes > 0 && file.bytes + incoming > max);
+        let too_old = self
+            .opts
+            .rotate_interval
+            .is_some_and(|max| file.opened.elapsed() >= max);
+
+        too_big || too_old
+    }
+
+    fn rotate(&mut self, path: &Path, mut file: OpenFile) -> io::Result<()> {
+        file.writer.flush()?;
+        drop(file);
+
+        let stamp = OffsetDateTime::now_utc()
+            .format(format_description!(
+                "[year][month][day]T[hour][minute][second].[subsecond digits:6]Z"
+            ))
+            .map_err(io::Error::other)?;
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(format!(".{stamp}"));
+        let rotated = PathBuf::from(rotated);
+
+        fs::rename(path, &rotated)?;
+
+        if self.opts.gzip {
+            // Reap what finished so the set doesn't grow over a long tail.
+            while let Some(done) = self.compressing.try_join_next() {
+                if let Err(e) = done.map_err(io::Error::other).and_then(|r| r) {
+                    tracing::warn!(error = %e, "failed to compress a rotated file");
+                }
+            }
+            self.compressing.spawn_blocking(move || gzip_file(&rotated));
+        }
+
+        Ok(())
+    }
+}
+
+impl Sink for SplitFiles {
+    fn write<'a>(&'a mut self, ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(std::future::ready(self.write_line(ev, line)))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(self.flush_all()))
+    }
+
+    /// Flushes every open file and waits for pending compression.
+    fn close(mut self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(async move {
+            self.flush_all()?;
+            while let Some(done) = self.compressing.join_next().await {
+                done.map_err(io::Error::other)??;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn open(path: &Path, header: Option<&str>) -> io::Result<OpenFile> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let f = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut file = OpenFile {
+        bytes: f.metadata()?.len(),
+        writer: BufWriter::new(f),
+        opened: Instant::now(),
+        written: Instant::now(),
+    };
+
+    if let (0, Some(header)) = (file.bytes, header) {
+        writeln!(file.writer, "{header}")?;
+        file.bytes += header.len() as u64 + 1;
+    }
+
+    Ok(file)
+}
+
+fn gzip_file(path: &Path) -> io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+
+    let mut input = File::open(path)?;
+    let output = File::create(&gz_path)?;
+    let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+
+    fs::remove_file(path)
+}
+
+fn sanitize(s: &str) -> String {
+    s.replace(['/', '\\'], "_")
+}
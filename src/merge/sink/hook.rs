@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::io;
+use std::process::Stdio;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::config::{HookAction, HookLimits};
+use crate::merge::format::format_ts;
+use crate::merge::sink::http::{https_client, HttpClient};
+use crate::merge::sink::Sink;
+use crate::types::LogEvent;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// How long the end of the run waits for actions still going.
+const CLOSE_GRACE: Duration = Duration::from_secs(10);
+
+/// Fires an `--on-match` action for each line its route lets through, as
+/// often as the limits allow. Actions run in the background so a slow hook
+/// never holds up the output.
+pub struct HookSink {
+    pattern: String,
+    action: Action,
+    limits: HookLimits,
+    last: Option<Instant>,
+    /// When the rule fired within the last minute
+    fired: VecDeque<Instant>,
+    /// Matches skipped since the rule last fired
+    suppressed: u64,
+    running: JoinSet<()>,
+}
+
+enum Action {
+    Command(String),
+    Webhook(Box<HttpClient>, http::Uri),
+}
+
+impl HookSink {
+    pub fn new(pattern: &str, action: &HookAction, limits: HookLimits) -> io::Result<Self> {
+        let action = match action {
+            HookAction::Command(cmd) => Action::Command(cmd.clone()),
+            HookAction::Webhook(url) => {
+                let uri = url.parse().map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidInput, format!("{url}: {e}"))
+                })?;
+                Action::Webhook(Box::new(https_client()?), uri)
+            }
+        };
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            action,
+            limits,
+            last: None,
+            fired: VecDeque::new(),
+            suppressed: 0,
+            running: JoinSet::new(),
+        })
+    }
+
+    fn allowed(&mut self, now: Instant) -> bool {
+        while self
+            .fired
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= MINUTE)
+        {
+            self.fired.pop_front();
+        }
+        let debounced = self
+            .last
+            .is_some_and(|t| now.duration_since(t) < self.limits.debounce);
+
+        !debounced && self.fired.len() < self.limits.max_per_minute as usize
+    }
+}
+
+impl Sink for HookSink {
+    fn write<'a>(&'a mut self, ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        // Reap what finished so the set doesn't grow over a long tail.
+        while self.running.try_join_next().is_some() {}
+
+        let now = Instant::now();
+        if !self.allowed(now) {
+            self.suppressed += 1;
+            return Box::pin(std::future::ready(Ok(())));
+        }
+        self.last = Some(now);
+        self.fired.push_back(now);
+        let suppressed = std::mem::take(&mut self.suppressed);
+
+        let json = line.to_string();
+        match &self.action {
+            Action::Command(cmd) => {
+                let vars = [
+                    ("KPL_PATTERN", self.pattern.clone()),
+                    ("KPL_KIND", ev.kind.name().to_string()),
+                    ("KPL_TS", format_ts(&ev.ts)),
+                    ("KPL_NAMESPACE", ev.namespace.clone()),
+                    ("KPL_POD", ev.pod.clone()),
+                    ("KPL_CONTAINER", ev.container.clone()),
+                    ("KPL_MESSAGE", ev.message.clone()),
+                    ("KPL_SUPPRESSED", suppressed.to_string()),
+                ];
+                self.running.spawn(run_command(cmd.clone(), vars, json));
+            }
+            Action::Webhook(client, uri) => {
+                self.running
+                    .spawn(post((**client).clone(), uri.clone(), json, suppressed));
+            }
+        }
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn close(mut self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(async move {
+            let running = async { while self.running.join_next().await.is_some() {} };
+            if tokio::time::timeout(CLOSE_GRACE, running).await.is_err() {
+                tracing::warn!(pattern = %self.pattern, "on-match actions still running at exit; abandoning them");
+            }
+            Ok(())
+        })
+    }
+
+    fn is_output(&self) -> bool {
+        false
+    }
+}
+
+async fn run_command(cmd: String, vars: [(&'static str, String); 8], json: String) {
+    let child = shell(&cmd)
+        .envs(vars)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            tracing::warn!(command = %cmd, error = %e, "on-match command failed to start");
+            return;
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Commands that don't read stdin close it early; that's fine.
+        let _ = stdin.write_all(json.as_bytes()).await;
+        let _ = stdin.write_all(b"\n").await;
+    }
+
+    match child.wait_with_output().await {
+        Ok(out) if out.status.success() => {}
+        Ok(out) => tracing::warn!(
+            command = %cmd,
+            status = %out.status,
+            stderr = %String::from_utf8_lossy(&out.stderr).trim(),
+            "on-match command failed"
+        ),
+        Err(e) => tracing::warn!(command = %cmd, error = %e, "on-match command failed"),
+    }
+}
+
+#[cfg(unix)]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("sh");
+    c.arg("-c").arg(cmd);
+    c
+}
+
+#[cfg(not(unix))]
+fn shell(cmd: &str) -> Command {
+    let mut c = Command::new("cmd");
+    c.arg("/C").arg(cmd);
+    c
+}
+
+async fn post(client: HttpClient, uri: http::Uri, json: String, suppressed: u64) {
+    let req = http::Request::post(uri.clone())
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .header(
+            http::header::USER_AGENT,
+            concat!("kpl/", env!("CARGO_PKG_VERSION")),
+        )
+        .header("x-kpl-suppressed", suppressed)
+        .body(Full::new(Bytes::from(json)));
+    let req = match req {
+        Ok(req) => req,
+        Err(e) => {
+            tracing::warn!(url = %uri, error = %e, "on-match webhook failed");
+            return;
+        }
+    };
+
+    match client.request(req).await {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => {
+            tracing::warn!(url = %uri, status = %resp.status(), "on-match webhook failed")
+        }
+        Err(e) => tracing::warn!(url = %uri, error = %e, "on-match webhook failed"),
+    }
+}
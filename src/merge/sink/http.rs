@@ -0,0 +1,225 @@
+use std::io;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use hyper_rustls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+
+use crate::merge::format::otlp;
+use crate::merge::sink::Sink;
+use crate::types::{LogEvent, OutputMode};
+
+const MAX_BATCH_LINES: usize = 512;
+
+/// How long a batch waits for more lines before it is sent. The merger's
+/// flushes don't apply: on a terminal they come after every write.
+const LINGER: Duration = Duration::from_secs(1);
+
+/// Lines waiting to be sent before new ones are dropped.
+const QUEUE_LINES: usize = 16 * MAX_BATCH_LINES;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tries per batch, waiting `FIRST_BACKOFF` and then twice as long each time.
+const MAX_ATTEMPTS: u32 = 3;
+const FIRST_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How long the end of the run waits for batches still being sent.
+const CLOSE_GRACE: Duration = Duration::from_secs(10);
+
+pub(crate) type HttpClient = Client<HttpsConnector<HttpConnector>, Full<Bytes>>;
+
+/// HTTP/1 over TLS or plain, trusting the system's roots.
+pub(crate) fn https_client() -> io::Result<HttpClient> {
+    let connector = hyper_rustls::HttpsConnectorBuilder::new()
+        .with_native_roots()?
+        .https_or_http()
+        .enable_http1()
+        .build();
+    Ok(Client::builder(TokioExecutor::new()).build(connector))
+}
+
+/// POSTs batches of lines to a URL. OTLP/JSON batches are merged into a
+/// single export request so the body stays valid for `/v1/logs`.
+///
+/// Lines are batched and sent by their own task behind a bounded queue, so
+/// a slow or failing endpoint costs lines sent there, never the other
+/// outputs.
+pub struct HttpSink {
+    uri: http::Uri,
+    queue: mpsc::Sender<String>,
+    /// Lines are being dropped; warned about once until the queue drains
+    behind: bool,
+    sender: JoinHandle<()>,
+}
+
+impl HttpSink {
+    pub fn new(url: &str, mode: OutputMode) -> io::Result<Self> {
+        let uri: http::Uri = url
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, format!("{url}: {e}")))?;
+
+        let (queue, rx) = mpsc::channel(QUEUE_LINES);
+        let sender = tokio::spawn(send_batches(https_client()?, uri.clone(), mode, rx));
+
+        Ok(Self {
+            uri,
+            queue,
+            behind: false,
+            sender,
+        })
+    }
+}
+
+impl Sink for HttpSink {
+    fn write<'a>(&'a mut self, _ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        match self.queue.try_send(line.to_string()) {
+            Ok(()) => self.behind = false,
+            Err(mpsc::error::TrySendError::Full(_)) if !self.behind => {
+                self.behind = true;
+                tracing::warn!(url = %self.uri, "http sink is behind; dropping lines");
+            }
+            Err(_) => {}
+        }
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    /// The sender task decides when a batch goes out.
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(async move {
+            let Self {
+                uri,
+                queue,
+                mut sender,
+                ..
+            } = *self;
+            // The task sends what is left and ends once the queue is closed.
+            drop(queue);
+
+            if tokio::time::timeout(CLOSE_GRACE, &mut sender)
+                .await
+                .is_err()
+            {
+                sender.abort();
+                tracing::warn!(url = %uri, "http sink still sending at exit; abandoning the rest");
+            }
+            Ok(())
+        })
+    }
+}
+
+async fn send_batches(
+    client: HttpClient,
+    uri: http::Uri,
+    mode: OutputMode,
+    mut rx: mpsc::Receiver<String>,
+) {
+    while let Some(lines) = next_batch(&mut rx).await {
+        let (body, content_type) = match mode {
+            OutputMode::OtlpJson => (otlp::merge_requests(&lines), "application/json"),
+            OutputMode::Json => (join_lines(&lines), "application/x-ndjson"),
+            _ => (join_lines(&lines), "text/plain; charset=utf-8"),
+        };
+        let body = Bytes::from(body);
+
+        let mut backoff = FIRST_BACKOFF;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match post(&client, &uri, content_type, body.clone()).await {
+                Ok(()) => break,
+                Err(e) if attempt < MAX_ATTEMPTS && e.retry => {
+                    tracing::debug!(url = %uri, attempt, error = %e.msg, "http sink retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => {
+                    tracing::warn!(url = %uri, lines = lines.len(), error = %e.msg, "http sink failed; dropping lines");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Waits for a line, then takes more until the batch is full, `LINGER` has
+/// passed or the queue is closed. `None` once the queue is closed and empty.
+async fn next_batch(rx: &mut mpsc::Receiver<String>) -> Option<Vec<String>> {
+    let first = rx.recv().await?;
+    let deadline = tokio::time::Instant::now() + LINGER;
+    let mut lines = Vec::with_capacity(MAX_BATCH_LINES);
+    lines.push(first);
+    while lines.len() < MAX_BATCH_LINES {
+        match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(line)) => lines.push(line),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    Some(lines)
+}
+
+struct PostError {
+    msg: String,
+    /// Worth trying again: the endpoint was unreachable, slow or overloaded
+    retry: bool,
+}
+
+async fn post(
+    client: &HttpClient,
+    uri: &http::Uri,
+    content_type: &str,
+    body: Bytes,
+) -> Result<(), PostError> {
+    let req = http::Request::post(uri.clone())
+        .header(http::header::CONTENT_TYPE, content_type)
+        .header(
+            http::header::USER_AGENT,
+            concat!("kpl/", env!("CARGO_PKG_VERSION")),
+        )
+        .body(Full::new(body))
+        .map_err(|e| PostError {
+            msg: e.to_string(),
+            retry: false,
+        })?;
+
+    let resp = match tokio::time::timeout(REQUEST_TIMEOUT, client.request(req)).await {
+        Ok(Ok(resp)) => resp,
+        Ok(Err(e)) => {
+            return Err(PostError {
+                msg: e.to_string(),
+                retry: true,
+            })
+        }
+        Err(_) => {
+            return Err(PostError {
+                msg: format!("no response after {}s", REQUEST_TIMEOUT.as_secs()),
+                retry: true,
+            })
+        }
+    };
+
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    Err(PostError {
+        msg: format!("responded {status}"),
+        retry: status.is_server_error()
+            || status == http::StatusCode::TOO_MANY_REQUESTS
+            || status == http::StatusCode::REQUEST_TIMEOUT,
+    })
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut body = lines.join("\n");
+    body.push('\n');
+    body
+}
@@ -0,0 +1,128 @@
+pub mod channel;
+pub mod file;
+pub mod files;
+pub mod hook;
+pub mod http;
+pub mod net;
+pub mod stats;
+pub mod stdout;
+
+use std::io;
+
+use futures::future::BoxFuture;
+
+use crate::config::{HookLimits, HookSpec, SinkFilter, SinkSpec, SinkTarget};
+use crate::merge::format::format_header;
+use crate::types::{ColorMode, LogEvent, OutputConfig, OutputMode};
+
+/// Destination for formatted log lines.
+///
+/// `write` may buffer; `flush` is called whenever the merger has drained the
+/// channel, and `close` once the run ends.
+pub trait Sink: Send {
+    fn write<'a>(&'a mut self, ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>>;
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>>;
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, io::Result<()>>;
+
+    /// Whether lines written here are read by someone. Sinks that only act
+    /// on lines (`--on-match`, `--stats`) don't keep the run going once
+    /// every output is gone.
+    fn is_output(&self) -> bool {
+        true
+    }
+}
+
+/// A sink together with the format and filter it was configured with.
+pub struct Route {
+    pub name: String,
+    pub output: OutputConfig,
+    pub filter: SinkFilter,
+    pub sink: Box<dyn Sink>,
+}
+
+pub async fn open_routes(specs: Vec<SinkSpec>, output: &OutputConfig) -> io::Result<Vec<Route>> {
+    let mut routes = Vec::with_capacity(specs.len());
+
+    for spec in specs {
+        let mut route_output = output.clone();
+        if let Some(mode) = spec.format {
+            route_output.mode = mode;
+        }
+        // Only the terminal gets ANSI colours.
+        if !matches!(spec.target, SinkTarget::Stdout) {
+            route_output.color = ColorMode::Never;
+        }
+
+        let header = format_header(&route_output);
+
+        let (name, sink): (String, Box<dyn Sink>) = match spec.target {
+            SinkTarget::Stdout => (
+                "stdout".to_string(),
+                Box::new(stdout::StdoutSink::new(header)?),
+            ),
+            SinkTarget::File(path) => (
+                format!("file:{}", path.display()),
+                Box::new(file::FileSink::open(&path, header)?),
+            ),
+            SinkTarget::Dir(opts) => (
+                format!("dir:{}", opts.dir.display()),
+                Box::new(files::SplitFiles::new(opts, header)?),
+            ),
+            SinkTarget::Tcp(addr) => (
+                format!("tcp:{addr}"),
+                Box::new(net::StreamSink::tcp(&addr, header).await?),
+            ),
+            SinkTarget::Unix(path) => (
+                format!("unix:{}", path.display()),
+                Box::new(net::StreamSink::unix(&path, header).await?),
+            ),
+            SinkTarget::Http(url) => (
+                url.clone(),
+                Box::new(http::HttpSink::new(&url, route_output.mode)?),
+            ),
+        };
+
+        routes.push(Route {
+            name,
+            output: route_output,
+            filter: spec.filter,
+            sink,
+        });
+    }
+
+    Ok(routes)
+}
+
+/// One route per `--on-match` rule: only the lines its regex matches, as
+/// JSON for the action.
+pub fn hook_routes(
+    hooks: &[HookSpec],
+    limits: &HookLimits,
+    output: &OutputConfig,
+) -> io::Result<Vec<Route>> {
+    let mut route_output = output.clone();
+    route_output.mode = OutputMode::Json;
+    route_output.color = ColorMode::Never;
+
+    hooks
+        .iter()
+        .map(|hook| {
+            let pattern = hook.pattern.as_str();
+            Ok(Route {
+                name: format!("on-match:{pattern}"),
+                output: route_output.clone(),
+                filter: SinkFilter {
+                    grep: Some(hook.pattern.clone()),
+                    ..Default::default()
+                },
+                sink: Box::new(hook::HookSink::new(
+                    pattern,
+                    &hook.action,
+                    hook.limits(limits),
+                )?),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,70 @@
+use std::io;
+use std::path::Path;
+
+use futures::future::BoxFuture;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+
+use crate::merge::sink::Sink;
+use crate::types::LogEvent;
+
+/// Newline-delimited lines over a TCP or Unix socket connection.
+pub struct StreamSink<W> {
+    writer: BufWriter<W>,
+}
+
+impl StreamSink<tokio::net::TcpStream> {
+    pub async fn tcp(addr: &str, header: Option<String>) -> io::Result<Self> {
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Self::with_header(stream, header).await
+    }
+}
+
+#[cfg(unix)]
+impl StreamSink<tokio::net::UnixStream> {
+    pub async fn unix(path: &Path, header: Option<String>) -> io::Result<Self> {
+        let stream = tokio::net::UnixStream::connect(path).await?;
+        Self::with_header(stream, header).await
+    }
+}
+
+#[cfg(not(unix))]
+impl StreamSink<tokio::net::TcpStream> {
+    pub async fn unix(_path: &Path, _header: Option<String>) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "unix socket sinks are only supported on unix",
+        ))
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send> StreamSink<W> {
+    async fn with_header(stream: W, header: Option<String>) -> io::Result<Self> {
+        let mut sink = Self {
+            writer: BufWriter::new(stream),
+        };
+        if let Some(header) = header {
+            sink.write_line(&header).await?;
+        }
+        Ok(sink)
+    }
+
+    async fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes()).await?;
+        self.writer.write_all(b"\n").await
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send + 'static> Sink for StreamSink<W> {
+    fn write<'a>(&'a mut self, _ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(self.write_line(line))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(self.writer.flush())
+    }
+
+    fn close(mut self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(async move { self.writer.shutdown().await })
+    }
+}
@@ -0,0 +1,39 @@
+use std::io;
+
+use futures::future::BoxFuture;
+
+use crate::merge::sink::Sink;
+use crate::stream::stats::Stats;
+use crate::types::LogEvent;
+
+/// Counts what the merger writes for `--stats`. The line is ignored.
+pub struct StatsSink {
+    stats: Stats,
+    /// `--stats-only`: the table is the output, so the run goes on for it
+    only: bool,
+}
+
+impl StatsSink {
+    pub fn new(stats: Stats, only: bool) -> Self {
+        Self { stats, only }
+    }
+}
+
+impl Sink for StatsSink {
+    fn write<'a>(&'a mut self, ev: &'a LogEvent, _line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        self.stats.record(ev);
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn close(self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(std::future::ready(Ok(())))
+    }
+
+    fn is_output(&self) -> bool {
+        self.only
+    }
+}
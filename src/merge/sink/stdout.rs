@@ -0,0 +1,46 @@
+use std::io::{self, BufWriter, Write};
+
+use futures::future::BoxFuture;
+
+use crate::merge::sink::Sink;
+use crate::types::LogEvent;
+
+const BUF_CAPACITY: usize = 64 * 1024;
+
+pub type StdoutSink = WriterSink<io::Stdout>;
+
+/// Buffers lines for a blocking writer. The buffer is written out when it
+/// fills up or when the merger flushes after a batch.
+pub struct WriterSink<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl StdoutSink {
+    pub fn new(header: Option<String>) -> io::Result<Self> {
+        WriterSink::with_writer(io::stdout(), header)
+    }
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn with_writer(inner: W, header: Option<String>) -> io::Result<Self> {
+        let mut writer = BufWriter::with_capacity(BUF_CAPACITY, inner);
+        if let Some(header) = header {
+            writeln!(writer, "{header}")?;
+        }
+        Ok(Self { writer })
+    }
+}
+
+impl<W: Write + Send + 'static> Sink for WriterSink<W> {
+    fn write<'a>(&'a mut self, _ev: &'a LogEvent, line: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(std::future::ready(writeln!(self.writer, "{line}")))
+    }
+
+    fn flush(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(std::future::ready(self.writer.flush()))
+    }
+
+    fn close(mut self: Box<Self>) -> BoxFuture<'static, io::Result<()>> {
+        Box::pin(std::future::ready(self.writer.flush()))
+    }
+}
@@ -0,0 +1,58 @@
+use regex::Regex;
+
+use crate::config::StopOpts;
+use crate::shutdown::ShutdownReason;
+use crate::types::{EventKind, LogEvent};
+
+/// Ends the run once `--until-match` or `--max-lines` is met, right after
+/// the line that met it is written. Only lines read from containers count;
+/// kpl's own lines (lifecycle, events, markers) never end the run.
+#[derive(Debug)]
+pub struct StopAt {
+    pattern: Option<Regex>,
+    max_lines: Option<u64>,
+    written: u64,
+}
+
+impl StopAt {
+    /// `None` when neither condition is set.
+    pub fn new(opts: &StopOpts) -> Option<Self> {
+        if opts.until_match.is_none() && opts.max_lines.is_none() {
+            return None;
+        }
+        Some(Self {
+            pattern: opts.until_match.clone(),
+            max_lines: opts.max_lines,
+            written: 0,
+        })
+    }
+
+    /// Counts a written line and says whether the run is over.
+    pub fn after(&mut self, ev: &LogEvent) -> Option<ShutdownReason> {
+        match ev.kind {
+            EventKind::Log => self.written += 1,
+            // The repeats were logged, but the line itself was already
+            // matched when it first went out.
+            EventKind::Repeated { count } => {
+                self.written += count;
+                return self.limit_reached();
+            }
+            _ => return None,
+        }
+        if self
+            .pattern
+            .as_ref()
+            .is_some_and(|re| re.is_match(&ev.message))
+        {
+            return Some(ShutdownReason::Matched);
+        }
+        self.limit_reached()
+    }
+
+    fn limit_reached(&self) -> Option<ShutdownReason> {
+        if self.max_lines.is_some_and(|max| self.written >= max) {
+            return Some(ShutdownReason::MaxLines);
+        }
+        None
+    }
+}
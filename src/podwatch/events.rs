@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::{pin_mut, StreamExt};
+use k8s_openapi::api::events::v1::Event;
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::api::ListParams;
+use kube::{Api, Client, ResourceExt};
+use kube_runtime::{watcher, WatchStreamExt};
+use time::OffsetDateTime;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::KubeLogOpts;
+use crate::errors::AppResult;
+use crate::podwatch::watcher::{classify, wants_container, Failure};
+use crate::stream::channel::LogTx;
+use crate::stream::status::PodNames;
+use crate::types::{ClusterEvent, EventKind, LogEvent};
+
+/// Watches the namespace's Events and forwards the ones about the pod
+/// watcher's pods into the merged stream. Events are extra context: failing
+/// to read them is logged and never ends the tail.
+pub fn spawn_event_watcher(
+    client: Client,
+    namespace: String,
+    opts: KubeLogOpts,
+    pods: PodNames,
+    tx: LogTx,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut events = Events {
+            pods,
+            cutoff: opts
+                .since
+                .map(|d| OffsetDateTime::now_utc() - d)
+                .unwrap_or(OffsetDateTime::UNIX_EPOCH),
+            opts: opts.clone(),
+            seen: HashMap::new(),
+            tx,
+        };
+        let api: Api<Event> = Api::namespaced(client, &namespace);
+
+        tokio::select! {
+            res = events.run(api, opts.follow) => {
+                if let Err(e) = res {
+                    tracing::warn!(error = %e, "event watcher failed");
+                }
+            }
+            _ = shutdown.cancelled() => {}
+        }
+    })
+}
+
+/// Only events about pods; deployments, nodes and the like are noise here.
+const POD_EVENTS: &str = "involvedObject.kind=Pod";
+
+struct Events {
+    pods: PodNames,
+    /// Older events are history the user didn't ask for (`--since`)
+    cutoff: OffsetDateTime,
+    opts: KubeLogOpts,
+    /// Event uid -> resource version last forwarded, so a relist or an
+    /// unrelated update doesn't repeat it
+    seen: HashMap<String, String>,
+    tx: LogTx,
+}
+
+impl Events {
+    async fn run(&mut self, api: Api<Event>, follow: bool) -> AppResult<()> {
+        if !follow {
+            let params = ListParams::default().fields(POD_EVENTS);
+            for event in api.list(&params).await? {
+                self.forward(&event).await;
+            }
+            return Ok(());
+        }
+
+        let stream = watcher(api, watcher::Config::default().fields(POD_EVENTS)).default_backoff();
+        pin_mut!(stream);
+
+        while let Some(item) = stream.next().await {
+            match item {
+                Ok(watcher::Event::Applied(event)) => self.forward(&event).await,
+                Ok(watcher::Event::Restarted(events)) => {
+                    for event in &events {
+                        self.forward(event).await;
+                    }
+                }
+                Ok(watcher::Event::Deleted(event)) => {
+                    if let Some(uid) = event.uid() {
+                        self.seen.remove(&uid);
+                    }
+                }
+                Err(e) if classify(&e) == Failure::Fatal => {
+                    tracing::warn!(error = %e, "not allowed to watch events; continuing without --events");
+                    return Ok(());
+                }
+                Err(e) => tracing::warn!(error = %e, "event watch failed; retrying"),
+            }
+        }
+        Ok(())
+    }
+
+    async fn forward(&mut self, event: &Event) {
+        let Some(ev) = log_event(event) else {
+            return;
+        };
+        if ev.ts < self.cutoff {
+            return;
+        }
+
+        if let (Some(uid), Some(version)) = (event.uid(), event.resource_version()) {
+            if self.seen.get(&uid) == Some(&version) {
+                return;
+            }
+            self.seen.insert(uid, version);
+        }
+
+        // Waits for the pod watcher's first listing.
+        let wanted = match self.pods.wait_for(Option::is_some).await {
+            Ok(pods) => pods
+                .as_ref()
+                .is_some_and(|pods| wanted(&ev, pods, &self.opts)),
+            Err(_) => false,
+        };
+        if wanted {
+            // A closed channel means the run is over.
+            let _ = self.tx.send(ev).await;
+        }
+    }
+}
+
+/// Whether an event is about one of `pods` and, when it names a container,
+/// one that `--container` and `--exclude-container` let through.
+pub fn wanted(ev: &LogEvent, pods: &HashSet<String>, opts: &KubeLogOpts) -> bool {
+    pods.contains(&ev.pod) && (ev.container.is_empty() || wants_container(&ev.container, opts))
+}
+
+/// The merged-stream entry for an Event about a pod, or `None` for events
+/// about anything else.
+pub fn log_event(event: &Event) -> Option<LogEvent> {
+    let regarding = event.regarding.as_ref()?;
+    if regarding.kind.as_deref() != Some("Pod") {
+        return None;
+    }
+
+    let series = event.series.as_ref();
+    let when = series
+        .map(|s| s.last_observed_time.0)
+        .or(event.event_time.as_ref().map(|t| t.0))
+        .or(event.deprecated_last_timestamp.as_ref().map(|t| t.0))
+        .or(event.metadata.creation_timestamp.as_ref().map(|t| t.0));
+
+    let count = series
+        .map(|s| s.count)
+        .or(event.deprecated_count)
+        .unwrap_or(1)
+        .max(1) as u64;
+
+    Some(LogEvent {
+        ts: when.map_or_else(OffsetDateTime::now_utc, to_offset),
+        namespace: regarding
+            .namespace
+            .clone()
+            .or_else(|| event.metadata.namespace.clone())
+            .unwrap_or_default(),
+        pod: regarding.name.clone()?,
+        container: regarding
+            .field_path
+            .as_deref()
+            .and_then(container_of)
+            .unwrap_or_default()
+            .to_string(),
+        message: event.note.clone().unwrap_or_default(),
+        meta: None,
+        kind: EventKind::Event(Box::new(ClusterEvent {
+            event_type: event.type_.clone().unwrap_or_else(|| "Normal".to_string()),
+            reason: event.reason.clone().unwrap_or_default(),
+            count,
+            reporter: event.reporting_controller.clone().or_else(|| {
+                event
+                    .deprecated_source
+                    .as_ref()
+                    .and_then(|s| s.component.clone())
+            }),
+        })),
+    })
+}
+
+/// `spec.containers{app}` -> `app`
+fn container_of(field_path: &str) -> Option<&str> {
+    let (_, rest) = field_path.split_once('{')?;
+    rest.strip_suffix('}')
+}
+
+fn to_offset(t: DateTime<Utc>) -> OffsetDateTime {
+    t.timestamp_nanos_opt()
+        .and_then(|n| OffsetDateTime::from_unix_timestamp_nanos(n.into()).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc)
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use k8s_openapi::api::core::v1::{ContainerStatus, Pod};
+use kube::ResourceExt;
+use time::OffsetDateTime;
+
+use crate::types::{Change, EventKind, Lifecycle, LogEvent, PodKey};
+
+/// Turns the pod watcher's updates into lifecycle lines such as
+/// `+ checkout-7f9 started` or `- checkout-7f9/app OOMKilled (exit 137)`.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    /// By pod uid
+    pods: HashMap<String, PodState>,
+}
+
+#[derive(Debug, Default)]
+struct PodState {
+    ready: bool,
+    /// Restart count per container
+    restarts: HashMap<String, i32>,
+    /// The last termination reported per container
+    terminated: HashMap<String, String>,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records pods that were already there, without announcing them.
+    pub fn baseline(&mut self, pods: &[Pod]) {
+        for pod in pods {
+            if let Some(uid) = pod.uid() {
+                self.pods.insert(uid, current_state(pod));
+            }
+        }
+    }
+
+    /// Lines for what changed since the pod was last seen.
+    pub fn applied(&mut self, pod: &Pod) -> Vec<LogEvent> {
+        let Some(uid) = pod.uid() else {
+            return Vec::new();
+        };
+        let namespace = pod.namespace().unwrap_or_default();
+        let name = pod.name_any();
+        let mut out = Vec::new();
+
+        let prev = match self.pods.remove(&uid) {
+            Some(prev) => prev,
+            None => {
+                out.push(line(
+                    &namespace,
+                    &name,
+                    "",
+                    just(Change::Started),
+                    format!("+ {name} started"),
+                ));
+                // Whatever happened before it was seen isn't news.
+                PodState {
+                    ready: false,
+                    ..current_state(pod)
+                }
+            }
+        };
+        let now = current_state(pod);
+
+        for (container, status) in container_statuses(pod) {
+            if let Some((id, reason, code)) = termination(status) {
+                if prev.terminated.get(container) != Some(&id) {
+                    out.push(line(
+                        &namespace,
+                        &name,
+                        container,
+                        Lifecycle {
+                            reason: Some(reason.clone()),
+                            exit_code: Some(code),
+                            ..just(Change::Terminated)
+                        },
+                        format!("- {name}/{container} {reason} (exit {code})"),
+                    ));
+                }
+            }
+
+            let before = prev.restarts.get(container).copied().unwrap_or(0);
+            if status.restart_count > before {
+                out.push(line(
+                    &namespace,
+                    &name,
+                    container,
+                    Lifecycle {
+                        restarts: Some(status.restart_count),
+                        ..just(Change::Restarted)
+                    },
+                    format!(
+                        "↻ {name}/{container} restarted (restart {})",
+                        status.restart_count
+                    ),
+                ));
+            }
+        }
+
+        if now.ready && !prev.ready {
+            out.push(line(
+                &namespace,
+                &name,
+                "",
+                just(Change::Ready),
+                format!("+ {name} ready"),
+            ));
+        }
+
+        self.pods.insert(uid, now);
+        out
+    }
+
+    pub fn deleted(&mut self, pod: &PodKey) -> Vec<LogEvent> {
+        self.pods.remove(&pod.uid);
+        vec![line(
+            &pod.namespace,
+            &pod.name,
+            "",
+            just(Change::Deleted),
+            format!("- {} deleted", pod.name),
+        )]
+    }
+}
+
+fn current_state(pod: &Pod) -> PodState {
+    let mut state = PodState {
+        ready: is_ready(pod),
+        ..Default::default()
+    };
+    for (container, status) in container_statuses(pod) {
+        state
+            .restarts
+            .insert(container.to_string(), status.restart_count);
+        if let Some((id, _, _)) = termination(status) {
+            state.terminated.insert(container.to_string(), id);
+        }
+    }
+    state
+}
+
+pub(crate) fn is_ready(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.conditions.as_ref())
+        .is_some_and(|cs| cs.iter().any(|c| c.type_ == "Ready" && c.status == "True"))
+}
+
+fn container_statuses(pod: &Pod) -> impl Iterator<Item = (&str, &ContainerStatus)> {
+    pod.status
+        .as_ref()
+        .and_then(|s| s.container_statuses.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .map(|s| (s.name.as_str(), s))
+}
+
+/// The container's latest termination: an identity to report it once, its
+/// reason and its exit code.
+fn termination(status: &ContainerStatus) -> Option<(String, String, i32)> {
+    let t = status
+        .state
+        .as_ref()
+        .and_then(|s| s.terminated.as_ref())
+        .or(status
+            .last_state
+            .as_ref()
+            .and_then(|s| s.terminated.as_ref()))?;
+
+    let id = format!(
+        "{}@{}",
+        t.container_id.as_deref().unwrap_or_default(),
+        t.finished_at
+            .as_ref()
+            .map(|f| f.0.to_rfc3339())
+            .unwrap_or_default()
+    );
+    let reason = t.reason.clone().unwrap_or_else(|| {
+        if t.exit_code == 0 {
+            "Completed".to_string()
+        } else {
+            "Error".to_string()
+        }
+    });
+    Some((id, reason, t.exit_code))
+}
+
+fn just(change: Change) -> Lifecycle {
+    Lifecycle {
+        change,
+        reason: None,
+        exit_code: None,
+        restarts: None,
+    }
+}
+
+fn line(
+    namespace: &str,
+    pod: &str,
+    container: &str,
+    lifecycle: Lifecycle,
+    message: String,
+) -> LogEvent {
+    LogEvent {
+        ts: OffsetDateTime::now_utc(),
+        namespace: namespace.to_string(),
+        pod: pod.to_string(),
+        container: container.to_string(),
+        message,
+        meta: None,
+        kind: EventKind::Lifecycle(Box::new(lifecycle)),
+    }
+}
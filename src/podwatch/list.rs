@@ -0,0 +1,157 @@
+use std::io::{self, Write};
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::ListParams;
+use kube::{Api, ResourceExt};
+
+use crate::config::{Config, KubeLogOpts};
+use crate::errors::AppResult;
+use crate::podwatch::watcher::{held_for_ready, pick_containers};
+
+/// One line of `kpl pods`: a pod the tail would attach to.
+#[derive(Debug, Clone)]
+pub struct PodRow {
+    pub name: String,
+    /// The containers the tail would stream, after `-c` and `--exclude-container`
+    pub containers: Vec<String>,
+    pub phase: String,
+    pub ready: usize,
+    pub total: usize,
+    pub restarts: i32,
+    pub node: Option<String>,
+    /// Seconds since the pod was created
+    pub age_secs: Option<i64>,
+}
+
+/// Prints what `kpl -n NS -l SELECTOR` would stream right now.
+pub async fn print_pods(config: &Config) -> AppResult<()> {
+    let namespace = crate::kube::client::namespace(config).await?;
+    let selector = crate::kube::target::selector(config, &namespace).await?;
+    let pods = if config.dev_mode {
+        vec![crate::dev::pods::dev_pod(&namespace)]
+    } else {
+        let client = crate::kube::client::make_client(
+            config.kube.context.as_deref(),
+            config.kube.kubeconfig.as_deref(),
+        )
+        .await?;
+        let api: Api<Pod> = Api::namespaced(client, &namespace);
+        api.list(&ListParams::default().labels(&selector))
+            .await?
+            .items
+    };
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+    let mut rows: Vec<PodRow> = pods
+        .iter()
+        .filter_map(|pod| pod_row(pod, &config.kube, now))
+        .collect();
+    rows.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if rows.is_empty() {
+        eprintln!("no pods in namespace {namespace:?} match {selector:?}");
+        return Ok(());
+    }
+
+    let mut out = io::stdout().lock();
+    write_table(&rows, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Summarises a pod, or `None` when the watcher would skip it: no container
+/// survives the filters, or `--wait-for-ready` holds it back.
+pub fn pod_row(pod: &Pod, opts: &KubeLogOpts, now: i64) -> Option<PodRow> {
+    if held_for_ready(pod, opts) {
+        return None;
+    }
+    let containers = pick_containers(pod, opts);
+    if containers.is_empty() {
+        return None;
+    }
+
+    let status = pod.status.as_ref();
+    let statuses = status
+        .and_then(|s| s.container_statuses.as_deref())
+        .unwrap_or_default();
+
+    Some(PodRow {
+        name: pod.name_any(),
+        containers,
+        phase: status
+            .and_then(|s| s.phase.clone())
+            .unwrap_or_else(|| "Unknown".to_string()),
+        ready: statuses.iter().filter(|c| c.ready).count(),
+        total: pod.spec.as_ref().map_or(0, |s| s.containers.len()),
+        restarts: statuses.iter().map(|c| c.restart_count).sum(),
+        node: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+        age_secs: pod
+            .creation_timestamp()
+            .map(|t| (now - t.0.timestamp()).max(0)),
+    })
+}
+
+pub fn write_table(rows: &[PodRow], out: &mut impl Write) -> io::Result<()> {
+    let header = [
+        "POD",
+        "CONTAINERS",
+        "PHASE",
+        "READY",
+        "RESTARTS",
+        "NODE",
+        "AGE",
+    ];
+    let cells: Vec<[String; 7]> = rows
+        .iter()
+        .map(|r| {
+            [
+                r.name.clone(),
+                r.containers.join(","),
+                r.phase.clone(),
+                format!("{}/{}", r.ready, r.total),
+                r.restarts.to_string(),
+                r.node.clone().unwrap_or_else(|| "-".to_string()),
+                r.age_secs.map_or_else(|| "-".to_string(), format_age),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &cells {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.len());
+        }
+    }
+
+    let mut line = |cols: &[&str]| -> io::Result<()> {
+        let mut text = String::new();
+        for (i, (cell, w)) in cols.iter().zip(widths).enumerate() {
+            if i + 1 == cols.len() {
+                text.push_str(cell);
+            } else {
+                text.push_str(&format!("{cell:<w$}   "));
+            }
+        }
+        writeln!(out, "{}", text.trim_end())
+    };
+
+    line(&header)?;
+    for row in &cells {
+        line(&row.each_ref().map(String::as_str))?;
+    }
+    Ok(())
+}
+
+/// kubectl-style age: the largest unit, plus the next one while it's small.
+fn format_age(secs: i64) -> String {
+    let (m, h, d) = (secs / 60, secs / 3600, secs / 86400);
+    match secs {
+        s if s < 120 => format!("{s}s"),
+        s if s < 600 => format!("{m}m{}s", s % 60),
+        s if s < 3 * 3600 => format!("{m}m"),
+        s if s < 8 * 3600 => format!("{h}h{}m", m % 60),
+        s if s < 2 * 86400 => format!("{h}h"),
+        s if s < 8 * 86400 => format!("{d}d{}h", h % 24),
+        _ => format!("{d}d"),
+    }
+}
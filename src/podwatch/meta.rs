@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::ResourceExt;
+
+use crate::config::EnrichOpts;
+use crate::types::PodMeta;
+
+const POD_TEMPLATE_HASH: &str = "pod-template-hash";
+
+pub fn pod_meta(pod: &Pod, opts: &EnrichOpts) -> Option<Arc<PodMeta>> {
+    if !opts.enabled {
+        return None;
+    }
+
+    let replica_set = pod
+        .owner_references()
+        .iter()
+        .find(|o| o.kind == "ReplicaSet")
+        .map(|o| o.name.clone());
+
+    let deployment = replica_set
+        .as_deref()
+        .and_then(|rs| deployment_name(rs, pod.labels().get(POD_TEMPLATE_HASH)?));
+
+    let images = pod
+        .spec
+        .as_ref()
+        .map(|s| {
+            s.containers
+                .iter()
+                .filter_map(|c| Some((c.name.clone(), c.image.clone()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Arc::new(PodMeta {
+        node: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+        pod_ip: pod.status.as_ref().and_then(|s| s.pod_ip.clone()),
+        replica_set,
+        deployment,
+        labels: select(pod.labels(), &opts.labels),
+        annotations: select(pod.annotations(), &opts.annotations),
+        images,
+    }))
+}
+
+/// Deployments name their ReplicaSets `<deployment>-<pod-template-hash>`.
+fn deployment_name(replica_set: &str, hash: &str) -> Option<String> {
+    replica_set
+        .strip_suffix(hash)
+        .and_then(|s| s.strip_suffix('-'))
+        .map(str::to_string)
+}
+
+fn select(all: &BTreeMap<String, String>, keys: &[String]) -> BTreeMap<String, String> {
+    keys.iter()
+        .filter_map(|k| Some((k.clone(), all.get(k)?.clone())))
+        .collect()
+}
@@ -1 +1,6 @@
+pub mod events;
+pub mod lifecycle;
+pub mod list;
+pub mod meta;
+pub mod outcome;
 pub mod watcher;
@@ -0,0 +1,142 @@
+use std::fmt;
+
+use k8s_openapi::api::batch::v1::Job;
+use k8s_openapi::api::core::v1::Pod;
+use kube::ResourceExt;
+
+/// How the matched pods ended, for `--until-complete`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    pub containers: Vec<ContainerExit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerExit {
+    pub pod: String,
+    pub container: String,
+    pub reason: String,
+    pub exit_code: i32,
+}
+
+impl ContainerExit {
+    /// `pod/container`, or just the pod when the pod itself failed
+    pub fn name(&self) -> String {
+        if self.container.is_empty() {
+            self.pod.clone()
+        } else {
+            format!("{}/{}", self.pod, self.container)
+        }
+    }
+}
+
+impl Outcome {
+    pub fn failed(&self) -> usize {
+        self.containers.iter().filter(|c| c.exit_code != 0).count()
+    }
+}
+
+/// One line per container: `migrate-x7k/app  Completed  exit 0`.
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self
+            .containers
+            .iter()
+            .map(|c| c.name().chars().count())
+            .max()
+            .unwrap_or(0);
+        let reason = self
+            .containers
+            .iter()
+            .map(|c| c.reason.chars().count())
+            .max()
+            .unwrap_or(0);
+        for c in &self.containers {
+            writeln!(
+                f,
+                "{:name$}  {:reason$}  exit {}",
+                c.name(),
+                c.reason,
+                c.exit_code
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The pod's containers' exits once it has Succeeded or Failed.
+pub fn finished(pod: &Pod) -> Option<Vec<ContainerExit>> {
+    let status = pod.status.as_ref()?;
+    let phase = status.phase.as_deref()?;
+    if phase != "Succeeded" && phase != "Failed" {
+        return None;
+    }
+
+    let mut exits: Vec<ContainerExit> = status
+        .container_statuses
+        .iter()
+        .flatten()
+        .map(|s| {
+            let terminated = s.state.as_ref().and_then(|st| st.terminated.as_ref());
+            // A Failed pod can have containers that never ran.
+            let exit_code = terminated.map_or(i32::from(phase == "Failed"), |t| t.exit_code);
+            ContainerExit {
+                pod: pod.name_any(),
+                container: s.name.clone(),
+                reason: terminated
+                    .and_then(|t| t.reason.clone())
+                    .unwrap_or_else(|| {
+                        if exit_code == 0 { "Completed" } else { "Error" }.to_string()
+                    }),
+                exit_code,
+            }
+        })
+        .collect();
+
+    // Evicted and deadline-exceeded pods fail without a container exiting.
+    if phase == "Failed" && exits.iter().all(|c| c.exit_code == 0) {
+        exits.push(ContainerExit {
+            pod: pod.name_any(),
+            container: String::new(),
+            reason: status
+                .reason
+                .clone()
+                .unwrap_or_else(|| "Failed".to_string()),
+            exit_code: 1,
+        });
+    }
+    exits.sort_by(|a, b| a.container.cmp(&b.container));
+    Some(exits)
+}
+
+/// The Job a pod was created for, if any.
+pub fn owning_job(pod: &Pod) -> Option<String> {
+    pod.owner_references()
+        .iter()
+        .find(|o| o.kind == "Job" && o.controller == Some(true))
+        .map(|o| o.name.clone())
+}
+
+/// Whether a Job is still creating pods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// Neither Complete nor Failed yet; a failed pod may be replaced
+    Running,
+    Complete,
+    Failed,
+}
+
+pub fn job_state(job: &Job) -> JobState {
+    let done = |kind: &str| {
+        job.status
+            .as_ref()
+            .and_then(|s| s.conditions.as_ref())
+            .is_some_and(|cs| cs.iter().any(|c| c.type_ == kind && c.status == "True"))
+    };
+    if done("Complete") {
+        JobState::Complete
+    } else if done("Failed") {
+        JobState::Failed
+    } else {
+        JobState::Running
+    }
+}
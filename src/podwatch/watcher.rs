@@ -1,124 +1,520 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 
-use futures::{pin_mut, StreamExt};
+use futures::future::BoxFuture;
+use futures::{pin_mut, Stream, StreamExt};
+use k8s_openapi::api::batch::v1::Job;
 use k8s_openapi::api::core::v1::Pod;
+use kube::api::ListParams;
 use kube::{Api, Client, Resource, ResourceExt};
-use kube_runtime::watcher;
+use kube_runtime::{watcher, WatchStreamExt};
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
 
+use crate::config::{EnrichOpts, KubeLogOpts};
 use crate::errors::{AppError, AppResult};
-use crate::types::{PodCommand, PodKey};
+use crate::podwatch::lifecycle::{is_ready, Tracker};
+use crate::podwatch::meta::pod_meta;
+use crate::podwatch::outcome::{finished, job_state, owning_job, ContainerExit, JobState, Outcome};
+use crate::stream::status::{StatusBoard, WatcherState};
+use crate::types::{LogEvent, PodCommand, PodKey};
+
+/// The container `kubectl logs` picks when none is named.
+const DEFAULT_CONTAINER_ANNOTATION: &str = "kubectl.kubernetes.io/default-container";
+
+/// How often to repeat that no pod matches yet.
+const WAIT_REMINDER: Duration = Duration::from_secs(30);
+
+/// How often to ask again about a Job whose pods have all finished but which
+/// hasn't said whether it will retry.
+const JOB_RECHECK: Duration = Duration::from_secs(5);
 
 pub fn spawn_pod_watcher(
     client: Client,
     namespace: String,
     selector: String,
-    containers_filter: Vec<String>,
+    opts: KubeLogOpts,
+    enrich: EnrichOpts,
+    status: StatusBoard,
     tx: mpsc::Sender<PodCommand>,
-) -> JoinHandle<AppResult<()>> {
+) -> JoinHandle<AppResult<Option<Outcome>>> {
     tokio::spawn(async move {
+        let jobs: Api<Job> = Api::namespaced(client.clone(), &namespace);
         let api: Api<Pod> = Api::namespaced(client, &namespace);
 
-        let stream = watcher(api, watcher::Config::default().labels(&selector));
+        if !opts.follow {
+            // Without --follow only the pods that exist now are read.
+            let pods = api.list(&ListParams::default().labels(&selector)).await?;
+            let mut watch =
+                PodWatch::new(namespace, selector, opts, enrich, tx).with_status(status);
+            for pod in &pods {
+                if let Some(key) = watch.key(pod) {
+                    watch.start(key, pod).await;
+                }
+            }
+            watch.listed = true;
+            watch.publish_names();
+            if watch.attached.is_empty() {
+                tracing::info!("no {}", watch.matching());
+            }
+            return Ok(None);
+        }
+
+        let stream = watcher(api, watcher::Config::default().labels(&selector)).default_backoff();
+        PodWatch::new(namespace, selector.clone(), opts, enrich, tx)
+            .with_jobs(jobs)
+            .with_status(status)
+            .run(stream)
+            .await
+    })
+}
+
+/// Turns pod watch events into supervisor commands, remembering which pods
+/// it started so a relist can stop the ones that vanished meanwhile, and
+/// telling the user while nothing matches. With `--until-complete` it ends
+/// once every pod it knows has finished and their Jobs won't retry.
+pub struct PodWatch {
+    namespace: String,
+    selector: String,
+    opts: KubeLogOpts,
+    enrich: EnrichOpts,
+    tx: mpsc::Sender<PodCommand>,
+    /// Every pod seen and not yet deleted, by uid
+    known: HashMap<String, PodKey>,
+    /// Uids of the pods whose logs are being read
+    attached: HashSet<String>,
+    /// How each finished pod ended, by uid; kept after the pod is deleted
+    outcomes: HashMap<String, Vec<ContainerExit>>,
+    /// The Job each finished pod belongs to, by uid
+    owners: HashMap<String, String>,
+    jobs: Option<Box<dyn JobLookup>>,
+    status: Option<StatusBoard>,
+    /// When to ask again about Jobs that may still retry
+    job_recheck: Option<Instant>,
+    /// Since when no pod has been attached, once the initial listing is done
+    waiting_since: Option<Instant>,
+    lifecycle: Option<Tracker>,
+    /// The initial listing is done
+    listed: bool,
+}
+
+impl PodWatch {
+    pub fn new(
+        namespace: String,
+        selector: String,
+        opts: KubeLogOpts,
+        enrich: EnrichOpts,
+        tx: mpsc::Sender<PodCommand>,
+    ) -> Self {
+        Self {
+            lifecycle: opts.lifecycle.then(Tracker::new),
+            namespace,
+            selector,
+            opts,
+            enrich,
+            tx,
+            known: HashMap::new(),
+            attached: HashSet::new(),
+            outcomes: HashMap::new(),
+            owners: HashMap::new(),
+            jobs: None,
+            status: None,
+            job_recheck: None,
+            waiting_since: None,
+            listed: false,
+        }
+    }
+
+    /// Lets `--until-complete` ask the owning Job whether a failed pod will
+    /// be replaced. Without it the pods alone decide.
+    pub fn with_jobs(mut self, jobs: impl JobLookup + 'static) -> Self {
+        self.jobs = Some(Box::new(jobs));
+        self
+    }
+
+    /// Reports retries on the status board, where the TUI shows them, and
+    /// the known pods, which `--events` goes by.
+    pub fn with_status(mut self, status: StatusBoard) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    pub async fn run(
+        mut self,
+        stream: impl Stream<Item = Result<watcher::Event<Pod>, watcher::Error>>,
+    ) -> AppResult<Option<Outcome>> {
         pin_mut!(stream);
+        // Consecutive failures, reset by the next event
+        let mut failures = 0u32;
 
-        while let Some(item) = stream.next().await {
-            let ev = item.map_err(|e| AppError::Other(format!("watcher error: {e}")))?;
+        let mut reminder = tokio::time::interval_at(Instant::now() + WAIT_REMINDER, WAIT_REMINDER);
 
-            match ev {
-                watcher::Event::Applied(pod) => {
-                    let Some(uid) = pod.meta().uid.clone() else {
-                        continue;
-                    };
-                    let name = pod.name_any();
-
-                    let pod_key = PodKey {
-                        namespace: namespace.clone(),
-                        name,
-                        uid,
-                    };
-
-                    let containers = pick_containers(&pod, &containers_filter);
-
-                    if !containers.is_empty() {
-                        let _ = tx
-                            .send(PodCommand::StartPod {
-                                pod: pod_key,
-                                containers,
-                            })
-                            .await;
+        loop {
+            let give_up = self
+                .waiting_since
+                .zip(self.opts.exit_if_no_pods_after)
+                .map(|(since, limit)| since + limit);
+
+            let item = tokio::select! {
+                item = stream.next() => match item {
+                    Some(item) => item,
+                    None => return Ok(None),
+                },
+                _ = reminder.tick() => {
+                    if let Some(since) = self.waiting_since {
+                        let waited = Duration::from_secs(since.elapsed().as_secs());
+                        tracing::info!(
+                            "still waiting for {} after {}",
+                            self.matching(),
+                            humantime::format_duration(waited)
+                        );
+                    }
+                    continue;
+                }
+                _ = sleep_until(self.job_recheck.unwrap_or_else(Instant::now)), if self.job_recheck.is_some() => {
+                    if let Some(outcome) = self.completed().await {
+                        return Ok(Some(outcome));
                     }
+                    continue;
                 }
+                _ = sleep_until(give_up.unwrap_or_else(Instant::now)), if give_up.is_some() => {
+                    return Err(AppError::Other(format!(
+                        "no {} after {}",
+                        self.matching(),
+                        humantime::format_duration(self.opts.exit_if_no_pods_after.unwrap_or_default())
+                    )));
+                }
+            };
 
-                watcher::Event::Deleted(pod) => {
-                    let Some(uid) = pod.meta().uid.clone() else {
+            let ev = match item {
+                Ok(ev) => ev,
+                Err(e) => match classify(&e) {
+                    Failure::Fatal => return Err(e.into()),
+                    Failure::Expired => {
+                        tracing::info!("pod watch expired; relisting");
+                        continue;
+                    }
+                    Failure::Transient => {
+                        failures += 1;
+                        tracing::warn!(error = %e, attempt = failures, "pod watch failed; retrying");
+                        self.set_state(WatcherState::Retrying {
+                            failures,
+                            error: e.to_string(),
+                        });
                         continue;
-                    };
-                    let name = pod.name_any();
+                    }
+                },
+            };
+            if failures > 0 {
+                tracing::info!(failures, "pod watch recovered");
+                self.set_state(WatcherState::Watching);
+                failures = 0;
+            }
+
+            match ev {
+                watcher::Event::Applied(pod) => self.applied(&pod).await,
+                watcher::Event::Deleted(pod) => self.deleted(&pod).await,
+                watcher::Event::Restarted(pods) => self.relisted(&pods).await,
+            }
+            if let Some(outcome) = self.completed().await {
+                return Ok(Some(outcome));
+            }
 
-                    let pod_key = PodKey {
-                        namespace: namespace.clone(),
-                        name,
-                        uid,
-                    };
+            if !self.listed || !self.attached.is_empty() {
+                self.waiting_since = None;
+            } else if self.waiting_since.is_none() {
+                self.waiting_since = Some(Instant::now());
+                tracing::info!("waiting for {}", self.matching());
+            }
+        }
+    }
 
-                    let _ = tx.send(PodCommand::StopPod { pod: pod_key }).await;
-                }
+    fn publish_names(&self) {
+        if let Some(status) = self.status.as_ref().filter(|_| self.listed) {
+            status.set_pods(self.known.values().map(|key| key.name.clone()).collect());
+        }
+    }
+
+    fn set_state(&self, state: WatcherState) {
+        if let Some(status) = &self.status {
+            status.set_watcher(state);
+        }
+    }
 
-                watcher::Event::Restarted(pods) => {
-                    for pod in pods {
-                        let Some(uid) = pod.meta().uid.clone() else {
-                            continue;
-                        };
-                        let name = pod.name_any();
-
-                        let pod_key = PodKey {
-                            namespace: namespace.clone(),
-                            name,
-                            uid,
-                        };
-
-                        let containers = pick_containers(&pod, &containers_filter);
-                        if !containers.is_empty() {
-                            let _ = tx
-                                .send(PodCommand::StartPod {
-                                    pod: pod_key,
-                                    containers,
-                                })
-                                .await;
-                        }
+    /// Every known pod has finished, at least one did, and none of their
+    /// Jobs will create another. Failed attempts of a Job that went on to
+    /// complete don't count.
+    async fn completed(&mut self) -> Option<Outcome> {
+        let waiting = self.job_recheck.take().is_some();
+        if !self.opts.until_complete
+            || self.outcomes.is_empty()
+            || !self.known.keys().all(|uid| self.outcomes.contains_key(uid))
+        {
+            return None;
+        }
+
+        let mut retried = HashSet::new();
+        let jobs: HashSet<&String> = self.owners.values().collect();
+        for job in jobs {
+            match self.job_state(job).await {
+                JobState::Running => {
+                    if !waiting {
+                        tracing::info!(job = %job, "pods finished; waiting for the job to retry or give up");
                     }
+                    self.job_recheck = Some(Instant::now() + JOB_RECHECK);
+                    return None;
+                }
+                JobState::Complete => {
+                    retried.insert(job.clone());
                 }
+                JobState::Failed => {}
             }
         }
 
-        Ok(())
-    })
+        let mut containers: Vec<ContainerExit> = self
+            .outcomes
+            .iter()
+            .filter(|(uid, exits)| {
+                let superseded = self.owners.get(*uid).is_some_and(|j| retried.contains(j));
+                !(superseded && exits.iter().any(|c| c.exit_code != 0))
+            })
+            .flat_map(|(_, exits)| exits.iter().cloned())
+            .collect();
+        containers.sort_by(|a, b| (&a.pod, &a.container).cmp(&(&b.pod, &b.container)));
+        Some(Outcome { containers })
+    }
+
+    /// Where a Job stands. Without a lookup, or when the Job can't be read,
+    /// its pods decide.
+    async fn job_state(&self, name: &str) -> JobState {
+        let Some(jobs) = self.jobs.as_ref() else {
+            return JobState::Failed;
+        };
+        match jobs.job(name).await {
+            Ok(job) => job_state(&job),
+            Err(kube::Error::Api(resp)) if matches!(resp.code, 403 | 404) => {
+                tracing::debug!(job = %name, code = resp.code, "can't read job; going by its pods");
+                JobState::Failed
+            }
+            Err(e) => {
+                tracing::warn!(job = %name, error = %e, "failed to read job; asking again");
+                JobState::Running
+            }
+        }
+    }
+
+    /// `ready pods matching app=web in shop`
+    fn matching(&self) -> String {
+        let ready = if self.opts.wait_for_ready {
+            "ready "
+        } else {
+            ""
+        };
+        format!(
+            "{ready}pods matching {} in {}",
+            self.selector, self.namespace
+        )
+    }
+
+    async fn applied(&mut self, pod: &Pod) {
+        if let Some(tracker) = self.lifecycle.as_mut() {
+            annotate(&self.tx, tracker.applied(pod)).await;
+        }
+        if let Some(key) = self.key(pod) {
+            self.start(key, pod).await;
+        }
+    }
+
+    async fn deleted(&mut self, pod: &Pod) {
+        if let Some(key) = self.key(pod) {
+            self.stop(key).await;
+        }
+    }
+
+    /// A full listing, at startup or after the watch lost its place. Pods
+    /// deleted in between never got a Deleted event, so they are stopped
+    /// here.
+    async fn relisted(&mut self, pods: &[Pod]) {
+        let present: HashSet<String> = pods.iter().filter_map(|p| p.uid()).collect();
+        let vanished: Vec<PodKey> = self
+            .known
+            .iter()
+            .filter(|(uid, _)| !present.contains(*uid))
+            .map(|(_, key)| key.clone())
+            .collect();
+        for key in vanished {
+            self.stop(key).await;
+        }
+
+        if self.listed {
+            for pod in pods {
+                self.applied(pod).await;
+            }
+            return;
+        }
+
+        // The first listing is what was already running.
+        if let Some(tracker) = self.lifecycle.as_mut() {
+            tracker.baseline(pods);
+        }
+        for pod in pods {
+            if let Some(key) = self.key(pod) {
+                self.start(key, pod).await;
+            }
+        }
+        self.listed = true;
+        self.publish_names();
+    }
+
+    fn key(&self, pod: &Pod) -> Option<PodKey> {
+        Some(PodKey {
+            namespace: self.namespace.clone(),
+            name: pod.name_any(),
+            uid: pod.meta().uid.clone()?,
+        })
+    }
+
+    async fn start(&mut self, key: PodKey, pod: &Pod) {
+        if self.known.insert(key.uid.clone(), key.clone()).is_none() {
+            self.publish_names();
+        }
+        // Once attached a pod stays attached, ready or not.
+        if !self.attached.contains(&key.uid) && held_for_ready(pod, &self.opts) {
+            return;
+        }
+        let exits = finished(pod);
+        if let Some(exits) = exits.filter(|_| self.opts.until_complete) {
+            self.outcomes.insert(key.uid.clone(), exits);
+            if let Some(job) = owning_job(pod) {
+                self.owners.insert(key.uid.clone(), job);
+            }
+        }
+
+        let containers = pick_containers(pod, &self.opts);
+        if !containers.is_empty() {
+            self.attached.insert(key.uid.clone());
+            let _ = self
+                .tx
+                .send(PodCommand::StartPod {
+                    pod: key,
+                    containers,
+                    meta: pod_meta(pod, &self.enrich),
+                })
+                .await;
+        }
+    }
+
+    async fn stop(&mut self, key: PodKey) {
+        if self.known.remove(&key.uid).is_some() {
+            self.publish_names();
+        }
+        self.attached.remove(&key.uid);
+        if let Some(tracker) = self.lifecycle.as_mut() {
+            annotate(&self.tx, tracker.deleted(&key)).await;
+        }
+        let _ = self.tx.send(PodCommand::StopPod { pod: key }).await;
+    }
+}
+
+/// Reads the Job that owns finished pods, for `--until-complete`.
+pub trait JobLookup: Send + Sync {
+    fn job<'a>(&'a self, name: &'a str) -> BoxFuture<'a, kube::Result<Job>>;
 }
 
-fn pick_containers(pod: &Pod, filter: &[String]) -> Vec<String> {
+impl JobLookup for Api<Job> {
+    fn job<'a>(&'a self, name: &'a str) -> BoxFuture<'a, kube::Result<Job>> {
+        Box::pin(self.get(name))
+    }
+}
+
+/// What a watch error means for the tail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Credentials or RBAC; retrying won't help
+    Fatal,
+    /// 410 Gone: the watch fell behind and the watcher lists again
+    Expired,
+    /// Anything else, usually the network; retried with backoff
+    Transient,
+}
+
+pub fn classify(e: &watcher::Error) -> Failure {
+    let code = match e {
+        watcher::Error::InitialListFailed(kube::Error::Api(resp))
+        | watcher::Error::WatchStartFailed(kube::Error::Api(resp))
+        | watcher::Error::WatchFailed(kube::Error::Api(resp))
+        | watcher::Error::WatchError(resp) => resp.code,
+        _ => return Failure::Transient,
+    };
+    match code {
+        401 | 403 => Failure::Fatal,
+        410 => Failure::Expired,
+        _ => Failure::Transient,
+    }
+}
+
+async fn annotate(tx: &mpsc::Sender<PodCommand>, lines: Vec<LogEvent>) {
+    for ev in lines {
+        let _ = tx.send(PodCommand::Annotate(ev)).await;
+    }
+}
+
+pub(crate) fn pick_containers(pod: &Pod, opts: &KubeLogOpts) -> Vec<String> {
     let mut names: Vec<String> = pod
         .spec
         .as_ref()
         .map(|s| s.containers.iter().map(|c| c.name.clone()).collect())
         .unwrap_or_default();
 
-    if filter.is_empty() {
+    if opts.default_container_only {
+        let default = pod
+            .annotations()
+            .get(DEFAULT_CONTAINER_ANNOTATION)
+            .filter(|want| names.contains(want))
+            .or(names.first())
+            .cloned();
+        names = default.into_iter().collect();
+    }
+
+    filter_containers(&pod.name_any(), names, opts)
+}
+
+/// Applies `--container` and `--exclude-container` to a pod's containers.
+pub(crate) fn filter_containers(
+    pod: &str,
+    mut names: Vec<String>,
+    opts: &KubeLogOpts,
+) -> Vec<String> {
+    names.retain(|c| !opts.exclude_containers.contains(c));
+
+    if opts.containers.is_empty() {
         return names;
     }
 
-    let present: HashSet<String> = names.iter().cloned().collect();
-    for want in filter {
+    let present: HashSet<&String> = names.iter().collect();
+    for want in &opts.containers {
         if !present.contains(want) {
             tracing::warn!(
-                pod = %pod.name_any(),
+                pod = %pod,
                 container = %want,
                 "requested container not found in pod spec"
             );
         }
     }
 
-    names.retain(|c| filter.contains(c));
+    names.retain(|c| opts.containers.contains(c));
     names
 }
+
+/// Whether `--container` and `--exclude-container` let a container through.
+/// `--wait-for-ready` keeps this pod back until it is ready. A finished pod
+/// never becomes ready but its logs are all there.
+pub(crate) fn held_for_ready(pod: &Pod, opts: &KubeLogOpts) -> bool {
+    opts.wait_for_ready && !is_ready(pod) && finished(pod).is_none()
+}
+
+pub(crate) fn wants_container(name: &str, opts: &KubeLogOpts) -> bool {
+    !opts.exclude_containers.iter().any(|c| c == name)
+        && (opts.containers.is_empty() || opts.containers.iter().any(|c| c == name))
+}
@@ -0,0 +1,267 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches};
+use toml::{Table, Value};
+
+use crate::cli::Cli;
+use crate::errors::{AppError, AppResult};
+
+const PROJECT_FILE: &str = ".kpl.toml";
+
+/// Installed under this name, kubectl runs us for `kubectl kpl`.
+const PLUGIN_NAME: &str = "kubectl-kpl";
+
+/// Settings that only make sense on the command line.
+const CLI_ONLY: &[&str] = &["profile", "config"];
+
+/// Parses the process arguments with defaults from the config files.
+///
+/// Settings are keyed by long flag name (`buffer = 4096`,
+/// `exclude-container = ["istio-proxy"]`); `--on-match` rules may also be
+/// `[[on-match]]` tables with their own limits. Top-level keys are defaults and
+/// `[profile.NAME]` tables are applied on top of them with `-p NAME`. The
+/// project-local `.kpl.toml` wins over `~/.config/kpl/config.toml`, and
+/// flags or `KPL_*` variables win over both.
+pub fn parse_cli() -> AppResult<Cli> {
+    parse_cli_from(std::env::args_os().collect())
+}
+
+pub fn parse_cli_from(args: Vec<OsString>) -> AppResult<Cli> {
+    let plugin = args
+        .first()
+        .and_then(|arg0| Path::new(arg0).file_stem())
+        .is_some_and(|stem| stem == PLUGIN_NAME);
+    let cmd = if plugin {
+        Cli::command().name(PLUGIN_NAME).bin_name("kubectl kpl")
+    } else {
+        Cli::command()
+    };
+
+    // First pass only finds --profile/--config and which flags were given;
+    // anything it can't parse is reported by the real parse below.
+    let Ok(given) = cmd.clone().ignore_errors(true).try_get_matches_from(&args) else {
+        return Ok(parse(cmd, args, plugin));
+    };
+
+    let mut files = Vec::new();
+    match given.get_one::<PathBuf>("config") {
+        Some(path) => files.push(path.clone()),
+        None => files.extend(user_config().filter(|p| p.is_file())),
+    }
+    files.extend(project_config());
+
+    let mut settings = Table::new();
+    let mut profiles: BTreeMap<String, Table> = BTreeMap::new();
+    for path in &files {
+        let (defaults, file_profiles) = load(path)?;
+        settings.extend(defaults);
+        for (name, profile) in file_profiles {
+            profiles.entry(name).or_default().extend(profile);
+        }
+    }
+
+    if let Some(name) = given.get_one::<String>("profile") {
+        let profile = profiles.remove(name).ok_or_else(|| {
+            let known: Vec<&str> = profiles.keys().map(String::as_str).collect();
+            AppError::Cli(format!(
+                "unknown profile {name:?} (available: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })?;
+        settings.extend(profile);
+    }
+
+    let mut from_files = Vec::new();
+    for (key, value) in settings {
+        let arg = cmd
+            .get_arguments()
+            .find(|a| a.get_long() == Some(key.as_str()))
+            .expect("keys are checked on load");
+
+        let explicit = matches!(
+            given.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        );
+        if !explicit {
+            push_setting(
+                &mut from_files,
+                &key,
+                value,
+                arg.get_action().takes_values(),
+            )?;
+        }
+    }
+
+    let mut args = args.into_iter();
+    let full: Vec<OsString> = args
+        .next()
+        .into_iter()
+        .chain(from_files)
+        .chain(args)
+        .collect();
+
+    Ok(parse(cmd, full, plugin))
+}
+
+/// The real parse; usage errors exit the way clap always does.
+fn parse(cmd: clap::Command, args: Vec<OsString>, plugin: bool) -> Cli {
+    let matches = cmd.get_matches_from(args);
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    cli.plugin = plugin;
+    cli
+}
+
+fn user_config() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".config")))?;
+    Some(base.join("kpl").join("config.toml"))
+}
+
+/// The nearest `.kpl.toml` in the current directory or its parents.
+fn project_config() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|p| p.is_file())
+}
+
+/// Reads a config file into its defaults and profiles, rejecting keys that
+/// aren't flags.
+fn load(path: &Path) -> AppResult<(Table, BTreeMap<String, Table>)> {
+    let err = |msg: String| AppError::Cli(format!("{}: {msg}", path.display()));
+
+    let text = std::fs::read_to_string(path).map_err(|e| err(e.to_string()))?;
+    let mut defaults: Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| err(e.to_string()))?;
+
+    let mut profiles = BTreeMap::new();
+    if let Some(value) = defaults.remove("profile") {
+        let Value::Table(tables) = value else {
+            return Err(err(
+                "`profile` must be a table of [profile.NAME] tables".into()
+            ));
+        };
+        for (name, value) in tables {
+            let Value::Table(profile) = value else {
+                return Err(err(format!("profile {name:?} must be a table")));
+            };
+            check_keys(&profile).map_err(|m| err(format!("profile {name:?}: {m}")))?;
+            profiles.insert(name, profile);
+        }
+    }
+    check_keys(&defaults).map_err(err)?;
+
+    Ok((defaults, profiles))
+}
+
+fn check_keys(table: &Table) -> Result<(), String> {
+    let cmd = Cli::command();
+    for key in table.keys() {
+        let known = cmd
+            .get_arguments()
+            .any(|a| a.get_long() == Some(key.as_str()));
+        if !known || CLI_ONLY.contains(&key.as_str()) {
+            return Err(format!("unknown setting {key:?}"));
+        }
+    }
+    Ok(())
+}
+
+/// Turns one setting back into the flags that would have set it.
+fn push_setting(
+    out: &mut Vec<OsString>,
+    key: &str,
+    value: Value,
+    takes_value: bool,
+) -> AppResult<()> {
+    let values = match value {
+        Value::Array(items) if takes_value => items,
+        Value::Boolean(on) if !takes_value => {
+            if on {
+                out.push(format!("--{key}").into());
+            }
+            return Ok(());
+        }
+        other if takes_value => vec![other],
+        other => {
+            return Err(AppError::Cli(format!(
+                "setting {key:?} is a switch and takes true or false, not {other}"
+            )))
+        }
+    };
+
+    for value in values {
+        let text = match value {
+            Value::Table(rule) if key == "on-match" => on_match_rule(rule)?,
+            Value::String(s) => s,
+            Value::Integer(_) | Value::Float(_) | Value::Boolean(_) => value.to_string(),
+            other => {
+                return Err(AppError::Cli(format!(
+                    "setting {key:?} has an unsupported value {other}"
+                )))
+            }
+        };
+        // `--key=value` so values starting with '-' aren't taken for flags.
+        out.push(format!("--{key}={text}").into());
+    }
+
+    Ok(())
+}
+
+/// An `[[on-match]]` table as the `--on-match` rule it stands for:
+///
+/// ```toml
+/// [[on-match]]
+/// match = "OOMKilled"
+/// run = "notify-send kpl OOM"   # or url = "https://..."
+/// debounce = "1m"
+/// max-per-minute = 2
+/// ```
+fn on_match_rule(mut rule: Table) -> AppResult<String> {
+    let err = |msg: String| AppError::Cli(format!("[[on-match]]: {msg}"));
+    let mut text = |name: &str| match rule.remove(name) {
+        Some(Value::String(s)) => Ok(Some(s)),
+        Some(other) => Err(err(format!("{name:?} must be a string, not {other}"))),
+        None => Ok(None),
+    };
+
+    let pattern = text("match")?.ok_or_else(|| err("\"match\" is required".into()))?;
+    let action = match (text("run")?, text("url")?) {
+        (Some(action), None) | (None, Some(action)) => action,
+        _ => return Err(err("needs one of \"run\" or \"url\"".into())),
+    };
+
+    let mut limits = Vec::new();
+    if let Some(debounce) = text("debounce")? {
+        limits.push(format!("debounce={debounce}"));
+    }
+    match rule.remove("max-per-minute") {
+        Some(Value::Integer(n)) => limits.push(format!("max-per-minute={n}")),
+        Some(other) => {
+            return Err(err(format!(
+                "\"max-per-minute\" must be a number, not {other}"
+            )))
+        }
+        None => {}
+    }
+    if let Some(key) = rule.keys().next() {
+        return Err(err(format!("unknown key {key:?}")));
+    }
+
+    let limits = if limits.is_empty() {
+        String::new()
+    } else {
+        format!("[{}]", limits.join(","))
+    };
+    Ok(format!("{limits}{}={action}", pattern.replace('=', "\\=")))
+}
@@ -1,15 +1,55 @@
 use tokio_util::sync::CancellationToken;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ShutdownReason {
     CtrlC,
     Sigterm,
     OutputClosed,
     WatcherEnded,
+    /// Every pod matched by --until-complete finished
+    Completed,
+    /// A line matched --until-match
+    Matched,
+    /// --until-match was set but the pods ended first
+    NoMatch,
+    /// --max-lines lines were written
+    MaxLines,
+    /// --timeout ran out
+    Timeout,
     WatcherError,
     OutputError,
 }
 
+impl ShutdownReason {
+    /// What the process exits with, so scripts can tell the stop
+    /// conditions apart. 2 is taken by configuration errors.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ShutdownReason::CtrlC
+            | ShutdownReason::Sigterm
+            | ShutdownReason::OutputClosed
+            | ShutdownReason::WatcherEnded
+            | ShutdownReason::Completed
+            | ShutdownReason::Matched => 0,
+            ShutdownReason::WatcherError | ShutdownReason::OutputError => 1,
+            ShutdownReason::MaxLines => 3,
+            ShutdownReason::NoMatch => 4,
+            // Like timeout(1)
+            ShutdownReason::Timeout => 124,
+        }
+    }
+
+    /// An `--until-match` run that ended on its own never saw the line.
+    pub fn unmatched(self, until_match: bool) -> Self {
+        match self {
+            ShutdownReason::WatcherEnded | ShutdownReason::Completed if until_match => {
+                ShutdownReason::NoMatch
+            }
+            reason => reason,
+        }
+    }
+}
+
 pub struct Shutdown {
     token: CancellationToken,
 }
@@ -0,0 +1,279 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use time::OffsetDateTime;
+use tokio::sync::Notify;
+
+use crate::types::{EventKind, LogEvent, OverflowPolicy};
+
+/// How often pending "lines dropped" markers are emitted while the channel
+/// stays full. They are emitted straight away once it drains.
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Under `sample`, one in this many lines per stream still gets in when the
+/// channel is full (evicting the oldest queued line).
+const SAMPLE_EVERY: u64 = 10;
+
+/// Bounded channel between the stream tasks and the merger that applies an
+/// [`OverflowPolicy`] when full, instead of always making senders wait.
+pub fn log_channel(capacity: usize, policy: OverflowPolicy) -> (LogTx, LogRx) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::with_capacity(capacity),
+            senders: 1,
+            closed: false,
+            dropped: HashMap::new(),
+            last_report: Instant::now(),
+        }),
+        capacity: capacity.max(1),
+        policy,
+        items: Notify::new(),
+        space: Notify::new(),
+    });
+
+    (
+        LogTx {
+            shared: shared.clone(),
+        },
+        LogRx { shared },
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+struct Shared {
+    state: Mutex<State>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    items: Notify,
+    space: Notify,
+}
+
+struct State {
+    queue: VecDeque<LogEvent>,
+    senders: usize,
+    closed: bool,
+    dropped: HashMap<StreamId, Dropped>,
+    last_report: Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct StreamId {
+    namespace: String,
+    pod: String,
+    container: String,
+}
+
+#[derive(Default)]
+struct Dropped {
+    /// Not yet reported
+    pending: u64,
+    /// Lines seen while full, for sampling
+    seen: u64,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl State {
+    fn drop_from(&mut self, ev: &LogEvent) -> &mut Dropped {
+        self.dropped.entry(StreamId::of(ev)).or_default()
+    }
+}
+
+impl StreamId {
+    fn of(ev: &LogEvent) -> Self {
+        Self {
+            namespace: ev.namespace.clone(),
+            pod: ev.pod.clone(),
+            container: ev.container.clone(),
+        }
+    }
+}
+
+pub struct LogTx {
+    shared: Arc<Shared>,
+}
+
+enum PushError {
+    Full(Box<LogEvent>),
+    Closed,
+}
+
+impl LogTx {
+    pub async fn send(&self, mut ev: LogEvent) -> Result<(), Closed> {
+        loop {
+            let space = self.shared.space.notified();
+            tokio::pin!(space);
+            space.as_mut().enable();
+
+            match self.try_push(ev) {
+                Ok(()) => {
+                    self.shared.items.notify_one();
+                    return Ok(());
+                }
+                Err(PushError::Closed) => return Err(Closed),
+                Err(PushError::Full(back)) => ev = *back,
+            }
+
+            space.await;
+        }
+    }
+
+    fn try_push(&self, ev: LogEvent) -> Result<(), PushError> {
+        let mut state = self.shared.lock();
+        if state.closed {
+            return Err(PushError::Closed);
+        }
+
+        if state.queue.len() < self.shared.capacity {
+            state.queue.push_back(ev);
+            return Ok(());
+        }
+
+        match self.shared.policy {
+            OverflowPolicy::Block => return Err(PushError::Full(Box::new(ev))),
+            OverflowPolicy::DropNewest => state.drop_from(&ev).pending += 1,
+            OverflowPolicy::DropOldest => {
+                evict_oldest(&mut state);
+                state.queue.push_back(ev);
+            }
+            OverflowPolicy::Sample => {
+                let d = state.drop_from(&ev);
+                d.seen += 1;
+                if d.seen % SAMPLE_EVERY == 0 {
+                    evict_oldest(&mut state);
+                    state.queue.push_back(ev);
+                } else {
+                    state.drop_from(&ev).pending += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn evict_oldest(state: &mut State) {
+    if let Some(old) = state.queue.pop_front() {
+        state.drop_from(&old).pending += 1;
+    }
+}
+
+impl Clone for LogTx {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for LogTx {
+    fn drop(&mut self) {
+        let last = {
+            let mut state = self.shared.lock();
+            state.senders -= 1;
+            state.senders == 0
+        };
+        if last {
+            self.shared.items.notify_one();
+        }
+    }
+}
+
+pub struct LogRx {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+impl LogRx {
+    pub async fn recv(&mut self) -> Option<LogEvent> {
+        let shared = self.shared.clone();
+        loop {
+            let items = shared.items.notified();
+            tokio::pin!(items);
+            items.as_mut().enable();
+
+            match self.try_recv() {
+                Ok(ev) => return Some(ev),
+                Err(TryRecvError::Disconnected) => return None,
+                Err(TryRecvError::Empty) => {}
+            }
+
+            items.await;
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<LogEvent, TryRecvError> {
+        let mut state = self.shared.lock();
+
+        let report_due =
+            state.queue.is_empty() || state.last_report.elapsed() >= DROP_REPORT_INTERVAL;
+        if report_due {
+            if let Some(marker) = take_marker(&mut state) {
+                return Ok(marker);
+            }
+        }
+        if state.queue.is_empty() {
+            // Pressure is gone; sampling starts over next time.
+            state.dropped.clear();
+        }
+
+        if let Some(ev) = state.queue.pop_front() {
+            drop(state);
+            self.shared.space.notify_one();
+            return Ok(ev);
+        }
+
+        if state.senders == 0 || state.closed {
+            Err(TryRecvError::Disconnected)
+        } else {
+            Err(TryRecvError::Empty)
+        }
+    }
+
+    /// Stops accepting new events; queued ones can still be received.
+    pub fn close(&mut self) {
+        self.shared.lock().closed = true;
+        self.shared.space.notify_waiters();
+    }
+}
+
+impl Drop for LogRx {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn take_marker(state: &mut State) -> Option<LogEvent> {
+    let id = state
+        .dropped
+        .iter()
+        .find(|(_, d)| d.pending > 0)
+        .map(|(id, _)| id.clone())?;
+
+    let d = state.dropped.get_mut(&id).expect("entry just found");
+    let count = std::mem::take(&mut d.pending);
+    state.last_report = Instant::now();
+
+    Some(LogEvent {
+        ts: OffsetDateTime::now_utc(),
+        message: format!("{count} lines dropped from {}/{}", id.pod, id.container),
+        namespace: id.namespace,
+        pod: id.pod,
+        container: id.container,
+        meta: None,
+        kind: EventKind::Dropped { count },
+    })
+}
@@ -1,14 +1,15 @@
-use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration};
 
 use time::OffsetDateTime;
 
-use crate::types::{LogEvent, PodKey};
+use crate::stream::limit::LimitedTx;
+use crate::types::{EventKind, LogEvent, MetaRx, PodKey};
 
 pub async fn dev_stream(
     pod: PodKey,
     container: String,
-    tx: mpsc::Sender<LogEvent>,
+    meta: MetaRx,
+    tx: &mut LimitedTx,
     rate_ms: u64,
     max_lines: Option<u64>,
 ) {
@@ -23,6 +24,8 @@ pub async fn dev_stream(
             pod: pod.name.clone(),
             container: container.clone(),
             message: format!("log line {}", counter),
+            meta: meta.borrow().clone(),
+            kind: EventKind::Log,
         };
 
         if tx.send(event).await.is_err() {
@@ -35,6 +38,20 @@ pub async fn dev_stream(
             }
         }
 
-        sleep(Duration::from_millis(rate_ms)).await;
+        if rate_ms == 0 {
+            // A zero sleep still waits for the next timer tick.
+            tokio::task::yield_now().await;
+        } else {
+            let next = sleep(Duration::from_millis(rate_ms));
+            tokio::pin!(next);
+            loop {
+                tokio::select! {
+                    _ = &mut next => break,
+                    res = tx.summary_due() => if res.is_err() {
+                        return;
+                    },
+                }
+            }
+        }
     }
 }
@@ -1,60 +1,88 @@
-use futures::AsyncBufReadExt;
+use futures::{AsyncBufReadExt, StreamExt};
 use kube::api::LogParams;
 use kube::Client;
-use tokio::sync::mpsc;
+use time::format_description::well_known::Rfc3339;
 use tokio_util::sync::CancellationToken;
 
 use crate::config::KubeLogOpts;
 use crate::errors::AppResult;
-use crate::types::{LogEvent, PodKey};
+use crate::stream::limit::LimitedTx;
+use crate::stream::status::{StatusBoard, StreamState};
+use crate::types::{EventKind, LogEvent, MetaRx, StreamKey};
 
 pub async fn kube_stream(
     client: Client,
-    pod: PodKey,
-    container: String,
-    _opts: KubeLogOpts,
-    tx: mpsc::Sender<LogEvent>,
+    key: StreamKey,
+    meta: MetaRx,
+    opts: &KubeLogOpts,
+    tx: &mut LimitedTx,
+    status: &StatusBoard,
     shutdown: CancellationToken,
 ) -> AppResult<()> {
+    let StreamKey { pod, container } = &key;
     let pods: kube::Api<k8s_openapi::api::core::v1::Pod> =
         kube::Api::namespaced(client, &pod.namespace);
 
     let lp = LogParams {
-        follow: true,
-        timestamps: false,
+        follow: opts.follow,
+        timestamps: opts.timestamps,
         container: Some(container.clone()),
+        tail_lines: opts.tail_lines,
+        since_seconds: opts.since.map(|d| d.as_secs().max(1) as i64),
         ..Default::default()
     };
 
-    let mut reader = pods.log_stream(&pod.name, &lp).await?;
+    let reader = pods.log_stream(&pod.name, &lp).await?;
+    status.set(&key, StreamState::Attached);
 
-    let mut line = String::new();
+    // Unlike read_line, the stream keeps a partly read line when the
+    // summary timer wins the select.
+    let mut lines = reader.lines();
 
     loop {
-        line.clear();
-
-        tokio::select! {
+        let line = tokio::select! {
             _ = shutdown.cancelled() => {
                 return Ok(());
             }
-
-            res = reader.read_line(&mut line) => {
-                let n = res?;
-                if n == 0 {
+            res = tx.summary_due() => {
+                // The merger is gone.
+                if res.is_err() {
                     return Ok(());
                 }
-                while line.ends_with('\n') || line.ends_with('\r') {
-                    line.pop();
-                }
-
-                let _ = tx.send(LogEvent {
-                    ts: time::OffsetDateTime::now_utc(),
-                    namespace: pod.namespace.clone(),
-                    pod: pod.name.clone(),
-                    container: container.clone(),
-                    message: line.clone(),
-                }).await;
+                continue;
             }
-        }
+            line = lines.next() => match line {
+                Some(line) => line?,
+                None => return Ok(()),
+            },
+        };
+        let line = line.trim_end_matches(['\n', '\r']);
+
+        let (ts, message) = if opts.timestamps {
+            split_timestamp(line)
+        } else {
+            (time::OffsetDateTime::now_utc(), line)
+        };
+
+        let meta = meta.borrow().clone();
+        let _ = tx
+            .send(LogEvent {
+                ts,
+                namespace: pod.namespace.clone(),
+                pod: pod.name.clone(),
+                container: container.clone(),
+                message: message.to_string(),
+                meta,
+                kind: EventKind::Log,
+            })
+            .await;
     }
 }
+
+/// Splits the RFC 3339 timestamp the API server puts in front of each line
+/// when asked for timestamps.
+fn split_timestamp(line: &str) -> (time::OffsetDateTime, &str) {
+    line.split_once(' ')
+        .and_then(|(ts, rest)| Some((time::OffsetDateTime::parse(ts, &Rfc3339).ok()?, rest)))
+        .unwrap_or_else(|| (time::OffsetDateTime::now_utc(), line))
+}
@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+use time::OffsetDateTime;
+use tokio::time::Instant;
+
+use crate::config::LimitOpts;
+use crate::stream::channel::{Closed, LogTx};
+use crate::types::{EventKind, LogEvent, MetaRx, StreamKey};
+
+/// Suppressed lines are summarised at most this often per stream.
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stream's handle on the log channel that applies `--max-lines-per-sec`
+/// and `--sample` before anything is queued.
+pub struct LimitedTx {
+    tx: LogTx,
+    key: StreamKey,
+    meta: MetaRx,
+    bucket: Option<TokenBucket>,
+    sample_every: Option<u64>,
+    seen: u64,
+    suppressed: u64,
+    last_summary: Instant,
+}
+
+impl LimitedTx {
+    pub fn new(tx: LogTx, key: StreamKey, meta: MetaRx, opts: &LimitOpts) -> Self {
+        Self {
+            tx,
+            key,
+            meta,
+            bucket: opts.max_lines_per_sec.map(TokenBucket::new),
+            sample_every: opts.sample_every.filter(|&n| n > 1),
+            seen: 0,
+            suppressed: 0,
+            last_summary: Instant::now(),
+        }
+    }
+
+    pub async fn send(&mut self, ev: LogEvent) -> Result<(), Closed> {
+        if !self.admit() {
+            self.suppressed += 1;
+            return Ok(());
+        }
+
+        if self.suppressed > 0 && self.last_summary.elapsed() >= SUMMARY_INTERVAL {
+            self.send_summary().await?;
+        }
+
+        self.tx.send(ev).await
+    }
+
+    /// Sends the summary once one is due. Never resolves while nothing is
+    /// suppressed, so a stream can wait on it next to its next line; a
+    /// stream that goes quiet still reports what it held back.
+    pub async fn summary_due(&mut self) -> Result<(), Closed> {
+        if self.suppressed == 0 {
+            return std::future::pending().await;
+        }
+        let due = self.last_summary + SUMMARY_INTERVAL;
+        tokio::time::sleep_until(due).await;
+        self.send_summary().await
+    }
+
+    /// Reports anything still suppressed when the stream ends.
+    pub async fn finish(mut self) {
+        if self.suppressed > 0 {
+            let _ = self.send_summary().await;
+        }
+    }
+
+    fn admit(&mut self) -> bool {
+        if let Some(n) = self.sample_every {
+            self.seen += 1;
+            if self.seen % n != 1 {
+                return false;
+            }
+        }
+
+        match self.bucket.as_mut() {
+            Some(bucket) => bucket.take(),
+            None => true,
+        }
+    }
+
+    /// Cancel safe: the count is only cleared once the summary is queued.
+    async fn send_summary(&mut self) -> Result<(), Closed> {
+        let count = self.suppressed;
+        let meta = self.meta.borrow().clone();
+
+        self.tx
+            .send(LogEvent {
+                ts: OffsetDateTime::now_utc(),
+                namespace: self.key.pod.namespace.clone(),
+                pod: self.key.pod.name.clone(),
+                container: self.key.container.clone(),
+                message: format!("{count} lines suppressed by rate limit/sampling"),
+                meta,
+                kind: EventKind::Suppressed { count },
+            })
+            .await?;
+        self.suppressed -= count;
+        self.last_summary = Instant::now();
+        Ok(())
+    }
+}
+
+/// Refills `rate` tokens per second, holding at most one second's worth.
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32) -> Self {
+        let rate = f64::from(rate.max(1));
+        Self {
+            rate,
+            tokens: rate,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.refilled = now;
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -1,3 +1,7 @@
+pub mod channel;
 pub mod dev;
 pub mod kube;
+pub mod limit;
+pub mod stats;
+pub mod status;
 pub mod supervisor;
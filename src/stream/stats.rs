@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::{IsTerminal, Write as _};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::StatsOpts;
+use crate::types::{EventKind, LogEvent, StreamKey};
+
+/// Namespace, pod and container; sorts the table.
+type Key = (String, String, String);
+
+/// Per-stream counters for `--stats`. The status board records attaches as
+/// the supervisor reports them; the merger's stats route counts what it
+/// writes. Cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    inner: Arc<Mutex<BTreeMap<Key, Counters>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    lines: u64,
+    bytes: u64,
+    errors: u64,
+    dropped: u64,
+    pending_since: Option<Instant>,
+    /// From start until the API server opened the log, for the latest attach
+    attach_latency: Option<Duration>,
+}
+
+/// One stream's counters at some point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub namespace: String,
+    pub pod: String,
+    pub container: String,
+    pub lines: u64,
+    pub bytes: u64,
+    pub errors: u64,
+    /// Lost to a full buffer, rate limits or sampling
+    pub dropped: u64,
+    pub attach_latency: Option<Duration>,
+}
+
+impl Stats {
+    pub fn pending(&self, key: &StreamKey) {
+        self.with(stream_key(key), |c| c.pending_since = Some(Instant::now()));
+    }
+
+    pub fn attached(&self, key: &StreamKey) {
+        self.with(stream_key(key), |c| {
+            c.attach_latency = c.pending_since.take().map(|t| t.elapsed());
+        });
+    }
+
+    /// Counts an event on its way out. Pod-level lines have no stream and
+    /// aren't counted.
+    pub fn record(&self, ev: &LogEvent) {
+        if ev.container.is_empty() {
+            return;
+        }
+        let key = (ev.namespace.clone(), ev.pod.clone(), ev.container.clone());
+        match ev.kind {
+            EventKind::Log => self.with(key, |c| {
+                c.lines += 1;
+                c.bytes += ev.message.len() as u64;
+                c.errors += u64::from(ev.is_error());
+            }),
+            EventKind::Repeated { count } => self.with(key, |c| c.lines += count),
+            EventKind::Dropped { count } | EventKind::Suppressed { count } => {
+                self.with(key, |c| c.dropped += count)
+            }
+            EventKind::Event(_) | EventKind::Lifecycle(_) => {}
+        }
+    }
+
+    pub fn rows(&self) -> Vec<Row> {
+        let inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        inner
+            .iter()
+            .map(|((namespace, pod, container), c)| Row {
+                namespace: namespace.clone(),
+                pod: pod.clone(),
+                container: container.clone(),
+                lines: c.lines,
+                bytes: c.bytes,
+                errors: c.errors,
+                dropped: c.dropped,
+                attach_latency: c.attach_latency,
+            })
+            .collect()
+    }
+
+    fn with(&self, key: Key, f: impl FnOnce(&mut Counters)) {
+        let mut inner = self.inner.lock().unwrap_or_else(PoisonError::into_inner);
+        f(inner.entry(key).or_default());
+    }
+}
+
+fn stream_key(key: &StreamKey) -> Key {
+    (
+        key.pod.namespace.clone(),
+        key.pod.name.clone(),
+        key.container.clone(),
+    )
+}
+
+/// Renders the counters as a table, with each stream's rate since the
+/// previous render.
+#[derive(Debug)]
+pub struct StatsTable {
+    prev: HashMap<(String, String), u64>,
+    at: Instant,
+}
+
+impl Default for StatsTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatsTable {
+    pub fn new() -> Self {
+        Self {
+            prev: HashMap::new(),
+            at: Instant::now(),
+        }
+    }
+
+    pub fn render(&mut self, rows: &[Row]) -> String {
+        let now = Instant::now();
+        let secs = now.duration_since(self.at).as_secs_f64().max(0.001);
+        self.at = now;
+
+        let header = [
+            "POD",
+            "CONTAINER",
+            "LINES",
+            "BYTES",
+            "LINES/S",
+            "ERRORS",
+            "DROPPED",
+            "ATTACH",
+        ];
+        let mut cells: Vec<[String; 8]> = vec![header.map(str::to_string)];
+        for row in rows {
+            let id = (row.pod.clone(), row.container.clone());
+            let before = self.prev.insert(id, row.lines).unwrap_or(0);
+            let rate = row.lines.saturating_sub(before) as f64 / secs;
+            cells.push([
+                row.pod.clone(),
+                row.container.clone(),
+                row.lines.to_string(),
+                bytes(row.bytes),
+                format!("{rate:.1}"),
+                row.errors.to_string(),
+                row.dropped.to_string(),
+                row.attach_latency
+                    .map_or_else(|| "-".to_string(), |d| format!("{}ms", d.as_millis())),
+            ]);
+        }
+
+        let mut widths = [0; 8];
+        for row in &cells {
+            for (w, cell) in widths.iter_mut().zip(row) {
+                *w = (*w).max(cell.chars().count());
+            }
+        }
+
+        let mut out = String::new();
+        for row in &cells {
+            let mut line = String::new();
+            for (i, (cell, w)) in row.iter().zip(widths).enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                // Names left, numbers right.
+                if i < 2 {
+                    let _ = write!(line, "{cell:<w$}");
+                } else {
+                    let _ = write!(line, "{cell:>w$}");
+                }
+            }
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn bytes(n: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if n < 1024 {
+        return n.to_string();
+    }
+    let mut v = n as f64;
+    let mut unit = "";
+    for u in UNITS {
+        if v < 1024.0 {
+            break;
+        }
+        v /= 1024.0;
+        unit = u;
+    }
+    format!("{v:.1}{unit}")
+}
+
+/// Prints the table every `--stats-interval` until `stop`, then hands the
+/// table back for the final print.
+pub async fn report(stats: Stats, opts: StatsOpts, stop: CancellationToken) -> StatsTable {
+    let mut table = StatsTable::new();
+    let Some(interval) = opts.interval else {
+        stop.cancelled().await;
+        return table;
+    };
+
+    let mut ticks = tokio::time::interval_at(Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = ticks.tick() => print(&mut table, &stats, opts.only),
+            _ = stop.cancelled() => return table,
+        }
+    }
+}
+
+/// `--stats-only` redraws the dashboard on stdout; `--stats` keeps the lines
+/// on stdout and puts the table on stderr.
+pub fn print(table: &mut StatsTable, stats: &Stats, only: bool) {
+    let out = table.render(&stats.rows());
+    // A closed pipe isn't worth failing the run over.
+    let _ = if only {
+        let mut stdout = std::io::stdout().lock();
+        if stdout.is_terminal() {
+            let _ = stdout.write_all(b"\x1b[H\x1b[2J");
+        }
+        stdout
+            .write_all(out.as_bytes())
+            .and_then(|()| stdout.flush())
+    } else {
+        std::io::stderr().lock().write_all(out.as_bytes())
+    };
+}
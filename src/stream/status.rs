@@ -0,0 +1,118 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+use crate::stream::stats::Stats;
+use crate::types::{PodKey, StreamKey};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamState {
+    /// Started, waiting for the API server to open the log
+    Pending,
+    Attached,
+    Failed(String),
+}
+
+/// Every stream the supervisor is running, or has seen fail.
+pub type StreamTable = HashMap<StreamKey, StreamState>;
+
+/// The names of the pods the pod watcher knows, attached or not; `None`
+/// until its first listing is done.
+pub type PodNames = watch::Receiver<Option<HashSet<String>>>;
+
+/// How the pod watch is doing. Its retries are otherwise only logged, and
+/// `--tui` hides the log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WatcherState {
+    #[default]
+    Watching,
+    /// The watch failed this many times in a row and is backing off
+    Retrying { failures: u32, error: String },
+}
+
+/// Where streams and the pod watcher report their state; the TUI watches it
+/// for its status bar and panes, `--stats` its attach counts and `--events`
+/// the pods. Cheap to clone and a no-op
+/// when nobody is subscribed.
+#[derive(Debug, Clone)]
+pub struct StatusBoard {
+    tx: Arc<watch::Sender<StreamTable>>,
+    watcher: Arc<watch::Sender<WatcherState>>,
+    pods: Arc<watch::Sender<Option<HashSet<String>>>>,
+    stats: Stats,
+}
+
+impl Default for StatusBoard {
+    fn default() -> Self {
+        Self {
+            tx: Arc::new(watch::Sender::new(StreamTable::new())),
+            watcher: Arc::new(watch::Sender::new(WatcherState::default())),
+            pods: Arc::new(watch::Sender::new(None)),
+            stats: Stats::default(),
+        }
+    }
+}
+
+impl StatusBoard {
+    pub fn subscribe(&self) -> watch::Receiver<StreamTable> {
+        self.tx.subscribe()
+    }
+
+    pub fn subscribe_watcher(&self) -> watch::Receiver<WatcherState> {
+        self.watcher.subscribe()
+    }
+
+    pub fn set_watcher(&self, state: WatcherState) {
+        self.watcher.send_if_modified(|current| {
+            if *current == state {
+                return false;
+            }
+            *current = state;
+            true
+        });
+    }
+
+    pub fn subscribe_pods(&self) -> PodNames {
+        self.pods.subscribe()
+    }
+
+    pub fn set_pods(&self, names: HashSet<String>) {
+        self.pods.send_replace(Some(names));
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats.clone()
+    }
+
+    pub fn set(&self, key: &StreamKey, state: StreamState) {
+        match state {
+            StreamState::Pending => self.stats.pending(key),
+            StreamState::Attached => self.stats.attached(key),
+            StreamState::Failed(_) => {}
+        }
+        self.tx.send_modify(|table| {
+            table.insert(key.clone(), state);
+        });
+    }
+
+    /// Forgets a stream that ended or was stopped. Failures stay listed.
+    pub fn remove(&self, key: &StreamKey) {
+        self.tx.send_if_modified(|table| {
+            if matches!(table.get(key), Some(StreamState::Failed(_)) | None) {
+                return false;
+            }
+            table.remove(key);
+            true
+        });
+    }
+
+    /// Forgets every stream of a pod that went away, failed or not.
+    pub fn remove_pod(&self, pod: &PodKey) {
+        self.tx.send_if_modified(|table| {
+            let before = table.len();
+            table.retain(|k, _| &k.pod != pod);
+            table.len() != before
+        });
+    }
+}
@@ -1,12 +1,16 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use kube::Client;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tokio_util::sync::CancellationToken;
 
-use crate::config::KubeLogOpts;
+use crate::config::{KubeLogOpts, LimitOpts};
 use crate::errors::AppError;
-use crate::types::{LogEvent, PodCommand, PodKey, StreamKey};
+use crate::stream::channel::LogTx;
+use crate::stream::limit::LimitedTx;
+use crate::stream::status::{StatusBoard, StreamState};
+use crate::types::{PodCommand, PodKey, PodMeta, StreamKey};
 
 #[derive(Clone)]
 pub enum StreamBackend {
@@ -16,43 +20,74 @@ pub enum StreamBackend {
     },
     Kube {
         client: Client,
-        opts: KubeLogOpts,
+        opts: Arc<KubeLogOpts>,
     },
 }
 
 pub struct StreamSupervisor {
     backend: StreamBackend,
-    log_tx: mpsc::Sender<LogEvent>,
+    log_tx: LogTx,
     fatal_tx: mpsc::Sender<AppError>,
+    limits: LimitOpts,
+    status: StatusBoard,
     shutdown: CancellationToken,
 
     streams: HashMap<StreamKey, CancellationToken>,
+    /// Each started pod's details, shared with its streams
+    metas: HashMap<PodKey, watch::Sender<Option<Arc<PodMeta>>>>,
 }
 
 impl StreamSupervisor {
     pub fn new(
-        log_tx: mpsc::Sender<LogEvent>,
+        log_tx: LogTx,
         fatal_tx: mpsc::Sender<AppError>,
         backend: StreamBackend,
+        limits: LimitOpts,
+        status: StatusBoard,
         shutdown: CancellationToken,
     ) -> Self {
         Self {
             backend,
             log_tx,
             fatal_tx,
+            limits,
+            status,
             shutdown,
             streams: HashMap::new(),
+            metas: HashMap::new(),
         }
     }
 
-    pub fn handle_command(&mut self, cmd: PodCommand) {
+    pub async fn handle_command(&mut self, cmd: PodCommand) {
         match cmd {
-            PodCommand::StartPod { pod, containers } => self.start_pod(pod, containers),
+            PodCommand::StartPod {
+                pod,
+                containers,
+                meta,
+            } => self.start_pod(pod, containers, meta),
             PodCommand::StopPod { pod } => self.stop_pod(pod),
+            PodCommand::Annotate(ev) => {
+                let _ = self.log_tx.send(ev).await;
+            }
         }
     }
 
-    fn start_pod(&mut self, pod: PodKey, containers: Vec<String>) {
+    fn start_pod(&mut self, pod: PodKey, containers: Vec<String>, meta: Option<Arc<PodMeta>>) {
+        // The watcher repeats StartPod on every change to the pod; running
+        // streams pick up what it has learnt since.
+        let meta_tx = self
+            .metas
+            .entry(pod.clone())
+            .or_insert_with(|| watch::Sender::new(None));
+        meta_tx.send_if_modified(|current| {
+            if *current == meta {
+                return false;
+            }
+            *current = meta;
+            true
+        });
+        let meta = meta_tx.subscribe();
+
         for container in containers {
             let key = StreamKey {
                 pod: pod.clone(),
@@ -65,8 +100,12 @@ impl StreamSupervisor {
 
             let token = self.shutdown.child_token();
             self.streams.insert(key.clone(), token.clone());
+            self.status.set(&key, StreamState::Pending);
+            let status = self.status.clone();
 
-            let log_tx = self.log_tx.clone();
+            let mut out =
+                LimitedTx::new(self.log_tx.clone(), key.clone(), meta.clone(), &self.limits);
+            let meta = meta.clone();
             let fatal_tx = self.fatal_tx.clone();
 
             match self.backend.clone() {
@@ -75,35 +114,43 @@ impl StreamSupervisor {
                     let container_clone = container.clone();
 
                     tokio::spawn(async move {
+                        status.set(&key, StreamState::Attached);
                         crate::stream::dev::dev_stream(
                             pod_clone,
                             container_clone,
-                            log_tx,
+                            meta,
+                            &mut out,
                             rate_ms,
                             max_lines,
                         )
                         .await;
+                        out.finish().await;
+                        status.remove(&key);
 
                         let _ = token;
                     });
                 }
 
                 StreamBackend::Kube { client, opts } => {
-                    let pod_clone = pod.clone();
-                    let container_clone = container.clone();
-
                     tokio::spawn(async move {
-                        if let Err(e) = crate::stream::kube::kube_stream(
+                        let res = crate::stream::kube::kube_stream(
                             client,
-                            pod_clone,
-                            container_clone,
-                            opts,
-                            log_tx,
+                            key.clone(),
+                            meta,
+                            &opts,
+                            &mut out,
+                            &status,
                             token,
                         )
-                        .await
-                        {
-                            let _ = fatal_tx.send(e).await;
+                        .await;
+                        out.finish().await;
+
+                        match res {
+                            Ok(()) => status.remove(&key),
+                            Err(e) => {
+                                status.set(&key, StreamState::Failed(e.to_string()));
+                                let _ = fatal_tx.send(e).await;
+                            }
                         }
                     });
                 }
@@ -112,6 +159,7 @@ impl StreamSupervisor {
     }
 
     fn stop_pod(&mut self, pod: PodKey) {
+        self.metas.remove(&pod);
         self.streams.retain(|k, token| {
             if k.pod == pod {
                 token.cancel();
@@ -120,6 +168,7 @@ impl StreamSupervisor {
                 true
             }
         });
+        self.status.remove_pod(&pod);
     }
 
     pub fn shutdown_all(&mut self) {
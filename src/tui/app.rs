@@ -0,0 +1,380 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use regex::Regex;
+
+use crate::stream::status::{StreamState, StreamTable, WatcherState};
+use crate::types::{LogEvent, StreamKey};
+
+/// Lines a PgUp/PgDn moves when the view height isn't known yet.
+const PAGE: usize = 20;
+
+/// What the event loop should do after a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Quit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Log,
+    Panel,
+}
+
+/// One row of the side panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    Pod(String),
+    Container(String, String),
+}
+
+/// Everything the TUI shows, independent of the terminal.
+pub struct App {
+    lines: VecDeque<LogEvent>,
+    capacity: usize,
+
+    /// How many visible lines the bottom of the view is above the newest one
+    pub scroll: usize,
+    pub paused: bool,
+    /// Lines that arrived while paused or scrolled back
+    pub unseen: usize,
+    pub page: usize,
+
+    pub filter: Option<Regex>,
+    /// The filter being typed, while `/` is open
+    pub input: Option<String>,
+    pub message: Option<String>,
+
+    /// (pod, container) pairs switched off in the panel
+    hidden: HashSet<(String, String)>,
+    known: BTreeMap<String, BTreeSet<String>>,
+    pub streams: StreamTable,
+    pub watcher: WatcherState,
+    pub focus: Focus,
+    pub selected: usize,
+    /// One pane per stream instead of the merged log
+    pub split: bool,
+
+    /// The merger is done; nothing more will arrive
+    pub ended: bool,
+}
+
+impl App {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity.min(4096)),
+            capacity: capacity.max(1),
+            scroll: 0,
+            paused: false,
+            unseen: 0,
+            page: PAGE,
+            filter: None,
+            input: None,
+            message: None,
+            hidden: HashSet::new(),
+            known: BTreeMap::new(),
+            streams: StreamTable::new(),
+            watcher: WatcherState::default(),
+            focus: Focus::Log,
+            selected: 0,
+            split: false,
+            ended: false,
+        }
+    }
+
+    pub fn following(&self) -> bool {
+        !self.paused && self.scroll == 0
+    }
+
+    pub fn push(&mut self, ev: LogEvent) {
+        // Lines about the whole pod get an entry of their own (the empty
+        // container), so the panel can hide them too.
+        self.known
+            .entry(ev.pod.clone())
+            .or_default()
+            .insert(ev.container.clone());
+
+        if !self.following() && self.is_visible(&ev) {
+            // Keep the frozen view where it is.
+            self.scroll += 1;
+            self.unseen += 1;
+        }
+
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(ev);
+    }
+
+    pub fn set_streams(&mut self, streams: StreamTable) {
+        for key in streams.keys() {
+            self.known
+                .entry(key.pod.name.clone())
+                .or_default()
+                .insert(key.container.clone());
+        }
+        self.streams = streams;
+    }
+
+    pub fn total(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_visible(&self, ev: &LogEvent) -> bool {
+        !self
+            .hidden
+            .contains(&(ev.pod.clone(), ev.container.clone()))
+            && self.filter.as_ref().map_or(true, |re| {
+                re.is_match(&ev.message) || re.is_match(&ev.pod) || re.is_match(&ev.container)
+            })
+    }
+
+    /// Lines that pass the filter and the panel, oldest first.
+    pub fn visible(&self) -> Vec<&LogEvent> {
+        self.lines.iter().filter(|ev| self.is_visible(ev)).collect()
+    }
+
+    /// The streams that get a pane in split view, in panel order.
+    pub fn panes(&self) -> Vec<&StreamKey> {
+        let mut keys: Vec<&StreamKey> = self
+            .streams
+            .keys()
+            .filter(|k| {
+                !self
+                    .hidden
+                    .contains(&(k.pod.name.clone(), k.container.clone()))
+            })
+            .collect();
+        keys.sort_by(|a, b| (&a.pod.name, &a.container).cmp(&(&b.pod.name, &b.container)));
+        keys
+    }
+
+    /// The last `height` visible lines of one stream, up to the bottom of
+    /// the merged view, so pausing and scrolling move every pane together.
+    pub fn pane_lines(&self, key: &StreamKey, height: usize) -> Vec<&LogEvent> {
+        let visible = self.visible();
+        let end = visible.len().saturating_sub(self.scroll);
+        let mut lines: Vec<&LogEvent> = visible[..end]
+            .iter()
+            .rev()
+            .filter(|ev| {
+                ev.pod == key.pod.name
+                    && (ev.container == key.container || ev.container.is_empty())
+                    && ev.namespace == key.pod.namespace
+            })
+            .take(height)
+            .copied()
+            .collect();
+        lines.reverse();
+        lines
+    }
+
+    /// Streams per state: attached, pending, failed.
+    pub fn stream_counts(&self) -> (usize, usize, usize) {
+        self.streams
+            .values()
+            .fold((0, 0, 0), |(a, p, f), state| match state {
+                StreamState::Attached => (a + 1, p, f),
+                StreamState::Pending => (a, p + 1, f),
+                StreamState::Failed(_) => (a, p, f + 1),
+            })
+    }
+
+    /// What the status bar says about a pod watch that keeps failing.
+    pub fn watcher_notice(&self) -> Option<String> {
+        match &self.watcher {
+            WatcherState::Watching => None,
+            WatcherState::Retrying { failures, error } => {
+                Some(format!("pod watch retrying ({failures} failed): {error}"))
+            }
+        }
+    }
+
+    /// The best state of any stream for this pod name and container.
+    pub fn state_of(&self, pod: &str, container: &str) -> Option<&StreamState> {
+        self.streams
+            .iter()
+            .filter(|(k, _)| k.pod.name == pod && k.container == container)
+            .map(|(_, s)| s)
+            .min_by_key(|s| match s {
+                StreamState::Attached => 0,
+                StreamState::Pending => 1,
+                StreamState::Failed(_) => 2,
+            })
+    }
+
+    pub fn entries(&self) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        for (pod, containers) in &self.known {
+            entries.push(Entry::Pod(pod.clone()));
+            for c in containers {
+                entries.push(Entry::Container(pod.clone(), c.clone()));
+            }
+        }
+        entries
+    }
+
+    pub fn is_shown(&self, entry: &Entry) -> bool {
+        match entry {
+            Entry::Pod(pod) => self.known.get(pod).is_some_and(|cs| {
+                cs.iter()
+                    .any(|c| !self.hidden.contains(&(pod.clone(), c.clone())))
+            }),
+            Entry::Container(pod, c) => !self.hidden.contains(&(pod.clone(), c.clone())),
+        }
+    }
+
+    fn toggle(&mut self, entry: &Entry) {
+        let pairs: Vec<(String, String)> = match entry {
+            Entry::Pod(pod) => self
+                .known
+                .get(pod)
+                .into_iter()
+                .flatten()
+                .map(|c| (pod.clone(), c.clone()))
+                .collect(),
+            Entry::Container(pod, c) => vec![(pod.clone(), c.clone())],
+        };
+
+        if self.is_shown(entry) {
+            self.hidden.extend(pairs);
+        } else {
+            for pair in &pairs {
+                self.hidden.remove(pair);
+            }
+        }
+        self.scroll = 0;
+    }
+
+    pub fn on_key(&mut self, key: KeyEvent) -> Action {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        if let Some(input) = self.input.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.input = None,
+                KeyCode::Enter => {
+                    let text = self.input.take().unwrap_or_default();
+                    self.apply_filter(&text);
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return Action::Continue;
+        }
+
+        self.message = None;
+        match (self.focus, key.code) {
+            (_, KeyCode::Char('q')) => return Action::Quit,
+            (_, KeyCode::Tab) => {
+                self.focus = match self.focus {
+                    Focus::Log => Focus::Panel,
+                    Focus::Panel => Focus::Log,
+                };
+            }
+            (_, KeyCode::Char('s')) => self.split = !self.split,
+            (_, KeyCode::Char('/')) => {
+                let current = self.filter.as_ref().map(|re| re.as_str().to_string());
+                self.input = Some(current.unwrap_or_default());
+            }
+
+            (Focus::Panel, KeyCode::Esc) => self.focus = Focus::Log,
+            (Focus::Panel, KeyCode::Up | KeyCode::Char('k')) => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            (Focus::Panel, KeyCode::Down | KeyCode::Char('j')) => {
+                let last = self.entries().len().saturating_sub(1);
+                self.selected = (self.selected + 1).min(last);
+            }
+            (Focus::Panel, KeyCode::Char(' ') | KeyCode::Enter) => {
+                if let Some(entry) = self.entries().get(self.selected).cloned() {
+                    self.toggle(&entry);
+                }
+            }
+
+            (Focus::Log, KeyCode::Char(' ')) => {
+                if self.following() {
+                    self.paused = true;
+                } else {
+                    self.resume();
+                }
+            }
+            (Focus::Log, KeyCode::Up | KeyCode::Char('k')) => self.scroll_by(1),
+            (Focus::Log, KeyCode::Down | KeyCode::Char('j')) => self.scroll_by(-1),
+            (Focus::Log, KeyCode::PageUp) => self.scroll_by(self.page as isize),
+            (Focus::Log, KeyCode::PageDown) => self.scroll_by(-(self.page as isize)),
+            (Focus::Log, KeyCode::Home | KeyCode::Char('g')) => {
+                self.paused = true;
+                self.scroll = self.visible().len();
+            }
+            (Focus::Log, KeyCode::End | KeyCode::Char('G')) => self.resume(),
+            (Focus::Log, KeyCode::Char('e')) => self.jump_to_error(true),
+            (Focus::Log, KeyCode::Char('E')) => self.jump_to_error(false),
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn apply_filter(&mut self, text: &str) {
+        if text.is_empty() {
+            self.filter = None;
+        } else {
+            match Regex::new(text) {
+                Ok(re) => self.filter = Some(re),
+                Err(e) => {
+                    let first = e.to_string();
+                    let first = first.lines().last().unwrap_or_default().to_string();
+                    self.message = Some(format!("bad filter: {first}"));
+                    return;
+                }
+            }
+        }
+        self.scroll = 0;
+    }
+
+    fn resume(&mut self) {
+        self.paused = false;
+        self.scroll = 0;
+        self.unseen = 0;
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let max = self.visible().len().saturating_sub(1);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+        if self.scroll > 0 {
+            self.paused = true;
+        } else if delta < 0 {
+            self.resume();
+        }
+    }
+
+    /// Moves the view so the previous (older) or next error line is at the
+    /// bottom.
+    fn jump_to_error(&mut self, older: bool) {
+        let (len, found) = {
+            let visible = self.visible();
+            let Some(bottom) = visible.len().checked_sub(1 + self.scroll) else {
+                return;
+            };
+            let found = if older {
+                (0..bottom).rev().find(|&i| visible[i].is_error())
+            } else {
+                (bottom + 1..visible.len()).find(|&i| visible[i].is_error())
+            };
+            (visible.len(), found)
+        };
+
+        match found {
+            Some(i) => {
+                self.scroll = len - 1 - i;
+                self.paused = true;
+            }
+            None => self.message = Some("no more errors".to_string()),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+pub mod app;
+pub mod view;
+
+use std::io;
+use std::time::Duration;
+
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures::StreamExt;
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+
+use crate::stream::status::{StreamTable, WatcherState};
+use crate::tui::app::{Action, App};
+use crate::types::LogEvent;
+
+/// Redraws at most this often while lines are arriving.
+const FRAME: Duration = Duration::from_millis(33);
+
+/// Runs the interactive view until the user quits, then cancels `shutdown`.
+///
+/// The view stays open after `events` closes so the scroll-back can still be
+/// read; the run ends when the user presses q.
+pub async fn run(
+    mut events: mpsc::Receiver<LogEvent>,
+    mut status: watch::Receiver<StreamTable>,
+    mut watcher: watch::Receiver<WatcherState>,
+    scrollback: usize,
+    shutdown: CancellationToken,
+) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let res = tokio::select! {
+        res = event_loop(&mut terminal, &mut events, &mut status, &mut watcher, scrollback) => res,
+        // SIGTERM, or the watcher giving up
+        _ = shutdown.cancelled() => Ok(()),
+    };
+    ratatui::restore();
+
+    shutdown.cancel();
+    res
+}
+
+async fn event_loop(
+    terminal: &mut ratatui::DefaultTerminal,
+    events: &mut mpsc::Receiver<LogEvent>,
+    status: &mut watch::Receiver<StreamTable>,
+    watcher: &mut watch::Receiver<WatcherState>,
+    scrollback: usize,
+) -> io::Result<()> {
+    let mut app = App::new(scrollback);
+    let mut keys = EventStream::new();
+    let mut tick = tokio::time::interval(FRAME);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut dirty = true;
+    let mut status_open = true;
+    let mut watcher_open = true;
+
+    loop {
+        tokio::select! {
+            key = keys.next() => match key {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if app.on_key(key) == Action::Quit {
+                        return Ok(());
+                    }
+                    // Keys redraw at once; lines wait for the next tick.
+                    terminal.draw(|f| view::draw(f, &mut app))?;
+                    dirty = false;
+                }
+                Some(Ok(Event::Resize(..))) => dirty = true,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            ev = events.recv(), if !app.ended => {
+                match ev {
+                    Some(ev) => {
+                        app.push(ev);
+                        while let Ok(ev) = events.try_recv() {
+                            app.push(ev);
+                        }
+                    }
+                    None => app.ended = true,
+                }
+                dirty = true;
+            }
+            changed = status.changed(), if status_open => {
+                match changed {
+                    Ok(()) => app.set_streams(status.borrow_and_update().clone()),
+                    Err(_) => status_open = false,
+                }
+                dirty = true;
+            }
+            changed = watcher.changed(), if watcher_open => {
+                match changed {
+                    Ok(()) => app.watcher = watcher.borrow_and_update().clone(),
+                    Err(_) => watcher_open = false,
+                }
+                dirty = true;
+            }
+            _ = tick.tick() => {
+                if dirty {
+                    terminal.draw(|f| view::draw(f, &mut app))?;
+                    dirty = false;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::merge::format::human::stable_color_index;
+use crate::stream::status::StreamState;
+use crate::tui::app::{App, Entry, Focus};
+use crate::types::{Change, EventKind, LogEvent, StreamKey, POD_LEVEL};
+
+const PANEL_WIDTH: u16 = 32;
+
+const LABEL_COLORS: [Color; 11] = [
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightYellow,
+    Color::LightRed,
+    Color::Blue,
+    Color::Green,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Yellow,
+];
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [panel, log] =
+        Layout::horizontal([Constraint::Length(PANEL_WIDTH), Constraint::Min(1)]).areas(main);
+
+    draw_panel(frame, app, panel);
+    if app.split {
+        draw_panes(frame, app, log);
+    } else {
+        draw_log(frame, app, log);
+    }
+    draw_status(frame, app, status);
+}
+
+fn draw_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let entries = app.entries();
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let check = if app.is_shown(entry) { "[x]" } else { "[ ]" };
+            let line = match entry {
+                Entry::Pod(pod) => Line::from(vec![
+                    Span::raw(format!("{check} ")),
+                    Span::styled(pod.clone(), Style::new().fg(label_color(pod))),
+                ]),
+                Entry::Container(pod, c) => {
+                    let (mark, color) = match app.state_of(pod, c) {
+                        Some(StreamState::Attached) => ("●", Color::Green),
+                        Some(StreamState::Pending) => ("○", Color::Yellow),
+                        Some(StreamState::Failed(_)) => ("✗", Color::Red),
+                        None => ("·", Color::DarkGray),
+                    };
+                    Line::from(vec![
+                        Span::raw(format!("  {check} ")),
+                        Span::styled(mark, Style::new().fg(color)),
+                        Span::raw(format!(" {}", if c.is_empty() { POD_LEVEL } else { c })),
+                    ])
+                }
+            };
+            ListItem::new(line)
+        })
+        .collect();
+
+    let focused = app.focus == Focus::Panel;
+    let list = List::new(items)
+        .block(titled_block(" streams ", focused))
+        .highlight_style(if focused {
+            Style::new().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::new()
+        });
+
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_log(frame: &mut Frame, app: &mut App, area: Rect) {
+    let block = titled_block(" logs ", app.focus == Focus::Log);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let height = inner.height as usize;
+    app.page = height.max(1);
+
+    let (lines, scroll) = {
+        let visible = app.visible();
+        let scroll = app.scroll.min(visible.len().saturating_sub(height));
+        let end = visible.len() - scroll;
+        let start = end.saturating_sub(height);
+        let lines: Vec<Line> = visible[start..end]
+            .iter()
+            .map(|ev| log_line(ev, true))
+            .collect();
+        (lines, scroll)
+    };
+    app.scroll = scroll;
+
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// A grid with a pane per stream, as square as the count allows.
+fn draw_panes(frame: &mut Frame, app: &mut App, area: Rect) {
+    app.page = area.height.saturating_sub(2).max(1) as usize;
+
+    let panes: Vec<StreamKey> = app.panes().into_iter().cloned().collect();
+    if panes.is_empty() {
+        let block = titled_block(" panes ", app.focus == Focus::Log);
+        let text = Paragraph::new("no streams attached").style(Style::new().fg(Color::DarkGray));
+        frame.render_widget(text.block(block), area);
+        return;
+    }
+
+    let cols = (panes.len() as f64).sqrt().ceil() as usize;
+    let rows = panes.len().div_ceil(cols);
+    let row_areas = Layout::vertical(vec![Constraint::Ratio(1, rows as u32); rows]).split(area);
+
+    for (row, chunk) in row_areas.iter().zip(panes.chunks(cols)) {
+        let cells = Layout::horizontal(vec![Constraint::Ratio(1, chunk.len() as u32); chunk.len()])
+            .split(*row);
+        for (cell, key) in cells.iter().zip(chunk) {
+            draw_pane(frame, app, key, *cell);
+        }
+    }
+}
+
+fn draw_pane(frame: &mut Frame, app: &App, key: &StreamKey, area: Rect) {
+    let border = match app.streams.get(key) {
+        Some(StreamState::Attached) => Color::DarkGray,
+        Some(StreamState::Pending) => Color::Yellow,
+        Some(StreamState::Failed(_)) | None => Color::Red,
+    };
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(Style::new().fg(border))
+        .title(Span::styled(
+            format!(" {}/{} ", key.pod.name, key.container),
+            Style::new().fg(label_color(&key.pod.name)),
+        ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // A failed stream keeps its last lines above the error.
+    let failure = match app.streams.get(key) {
+        Some(StreamState::Failed(e)) => Some(e.clone()),
+        _ => None,
+    };
+    let height = (inner.height as usize).saturating_sub(failure.is_some() as usize);
+    let mut lines: Vec<Line> = app
+        .pane_lines(key, height)
+        .into_iter()
+        .map(|ev| log_line(ev, false))
+        .collect();
+    if let Some(e) = failure {
+        lines.push(Line::styled(e, Style::new().fg(Color::Red)));
+    }
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+/// One event as a styled line; `label` adds the pod/container column.
+pub(crate) fn log_line(ev: &LogEvent, label: bool) -> Line<'static> {
+    let ts = ev
+        .ts
+        .format(time::macros::format_description!(
+            "[hour]:[minute]:[second].[subsecond digits:3]"
+        ))
+        .unwrap_or_default();
+
+    let mut spans = vec![
+        Span::styled(ts, Style::new().fg(Color::DarkGray)),
+        Span::raw(" "),
+    ];
+    let bar = if matches!(ev.kind, EventKind::Event(_)) {
+        "⚑"
+    } else {
+        "│"
+    };
+    if label {
+        let name = if ev.container.is_empty() {
+            ev.pod.clone()
+        } else {
+            format!("{}/{}", ev.pod, ev.container)
+        };
+        spans.push(Span::styled(name, Style::new().fg(label_color(&ev.pod))));
+        spans.push(Span::raw(format!(" {bar} ")));
+    } else {
+        spans.push(Span::raw(format!("{bar} ")));
+    }
+
+    if let EventKind::Event(e) = &ev.kind {
+        let color = if e.is_warning() {
+            Color::Yellow
+        } else {
+            Color::Cyan
+        };
+        spans.push(Span::styled(
+            format!("{} {}: ", e.event_type, e.reason),
+            Style::new().fg(color),
+        ));
+    }
+
+    let style = if let EventKind::Lifecycle(l) = &ev.kind {
+        let color = match l.change {
+            Change::Started | Change::Ready => Color::Green,
+            Change::Restarted => Color::Yellow,
+            Change::Terminated | Change::Deleted => Color::Red,
+        };
+        Style::new().fg(color).add_modifier(Modifier::BOLD)
+    } else if ev.kind.is_notice() {
+        Style::new().add_modifier(Modifier::DIM)
+    } else if ev.is_error() {
+        Style::new().fg(Color::Red)
+    } else {
+        Style::new()
+    };
+    spans.push(Span::styled(ev.message.clone(), style));
+
+    Line::from(spans)
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    if let Some(input) = &app.input {
+        let line = Line::from(vec![
+            Span::styled("filter /", Style::new().fg(Color::Yellow)),
+            Span::raw(input.clone()),
+            Span::styled("█", Style::new().fg(Color::Yellow)),
+        ]);
+        frame.render_widget(Paragraph::new(line), area);
+        return;
+    }
+
+    let (attached, pending, failed) = app.stream_counts();
+    let mode = if app.ended {
+        Span::styled(" ENDED ", Style::new().bg(Color::DarkGray).fg(Color::White))
+    } else if app.following() {
+        Span::styled(" FOLLOW ", Style::new().bg(Color::Green).fg(Color::Black))
+    } else {
+        Span::styled(
+            format!(" PAUSED +{} ", app.unseen),
+            Style::new().bg(Color::Yellow).fg(Color::Black),
+        )
+    };
+
+    let mut spans = vec![
+        mode,
+        Span::raw(format!(
+            " {attached} attached, {pending} pending, {failed} failed │ {} lines",
+            app.total()
+        )),
+    ];
+    if let Some(re) = &app.filter {
+        spans.push(Span::styled(
+            format!(" │ /{}/", re.as_str()),
+            Style::new().fg(Color::Yellow),
+        ));
+    }
+    if let Some(notice) = app.watcher_notice() {
+        spans.push(Span::styled(
+            format!(" │ {notice}"),
+            Style::new().fg(Color::Yellow),
+        ));
+    }
+    if let Some(msg) = &app.message {
+        spans.push(Span::styled(
+            format!(" │ {msg}"),
+            Style::new().fg(Color::Red),
+        ));
+    }
+    spans.push(Span::styled(
+        "  q quit · space pause · / filter · e/E errors · s split · tab streams",
+        Style::new().fg(Color::DarkGray),
+    ));
+
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+fn titled_block(title: &str, focused: bool) -> Block<'static> {
+    let style = if focused {
+        Style::new().fg(Color::Cyan)
+    } else {
+        Style::new().fg(Color::DarkGray)
+    };
+    Block::new()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(title.to_string())
+}
+
+fn label_color(key: &str) -> Color {
+    LABEL_COLORS[stable_color_index(key) % LABEL_COLORS.len()]
+}
@@ -1,6 +1,11 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 use time::OffsetDateTime;
+use tokio::sync::watch;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PodKey {
@@ -20,12 +25,19 @@ pub enum PodCommand {
     StartPod {
         pod: PodKey,
         containers: Vec<String>,
+        meta: Option<Arc<PodMeta>>,
     },
     StopPod {
         pod: PodKey,
     },
+    /// Put a line written by kpl itself into the merged stream
+    Annotate(LogEvent),
 }
 
+/// The name shown for lines about the whole pod (events, lifecycle), which
+/// have an empty `container`.
+pub const POD_LEVEL: &str = "_pod";
+
 #[derive(Debug, Clone)]
 pub struct LogEvent {
     pub ts: OffsetDateTime,
@@ -33,6 +45,175 @@ pub struct LogEvent {
     pub pod: String,
     pub container: String,
     pub message: String,
+    pub meta: Option<Arc<PodMeta>>,
+    pub kind: EventKind,
+}
+
+impl LogEvent {
+    /// A Warning event, or a line that looks like it reports an error.
+    pub fn is_error(&self) -> bool {
+        static ERROR: OnceLock<Regex> = OnceLock::new();
+        match &self.kind {
+            EventKind::Event(e) => e.is_warning(),
+            kind => {
+                !kind.is_notice()
+                    && ERROR
+                        .get_or_init(|| {
+                            Regex::new(r"(?i)\b(error|err|fatal|panic|exception|level=error)\b")
+                                .expect("static regex")
+                        })
+                        .is_match(&self.message)
+            }
+        }
+    }
+}
+
+/// What a [`LogEvent`] carries besides container output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum EventKind {
+    /// A line read from the container
+    Log,
+    /// Lines from this stream were lost to `--on-overflow`
+    Dropped { count: u64 },
+    /// Lines from this stream were held back by `--max-lines-per-sec`/`--sample`
+    Suppressed { count: u64 },
+    /// The line repeated this many more times (`--dedupe`)
+    Repeated { count: u64 },
+    /// A Kubernetes Event about the pod (`--events`); the note is the message
+    Event(Box<ClusterEvent>),
+    /// The pod watcher saw a pod or container change state
+    Lifecycle(Box<Lifecycle>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Change {
+    Started,
+    Ready,
+    Terminated,
+    Restarted,
+    Deleted,
+}
+
+impl Change {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Change::Started => "started",
+            Change::Ready => "ready",
+            Change::Terminated => "terminated",
+            Change::Restarted => "restarted",
+            Change::Deleted => "deleted",
+        }
+    }
+}
+
+/// What changed, for a [`EventKind::Lifecycle`] line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Lifecycle {
+    pub change: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restarts: Option<i32>,
+}
+
+/// The parts of an `events.k8s.io` Event that aren't already on the
+/// [`LogEvent`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClusterEvent {
+    /// Normal or Warning
+    pub event_type: String,
+    pub reason: String,
+    /// Times the API server has seen it
+    pub count: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reporter: Option<String>,
+}
+
+impl ClusterEvent {
+    pub fn is_warning(&self) -> bool {
+        self.event_type == "Warning"
+    }
+}
+
+impl EventKind {
+    pub fn is_log(&self) -> bool {
+        matches!(self, EventKind::Log)
+    }
+
+    /// Written by kpl itself rather than read from a container.
+    pub fn is_notice(&self) -> bool {
+        !matches!(
+            self,
+            EventKind::Log | EventKind::Repeated { .. } | EventKind::Event(_)
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Log => "log",
+            EventKind::Dropped { .. } => "dropped",
+            EventKind::Suppressed { .. } => "suppressed",
+            EventKind::Repeated { .. } => "repeated",
+            EventKind::Event(_) => "event",
+            EventKind::Lifecycle(_) => "lifecycle",
+        }
+    }
+
+    /// Number of lines a marker stands for.
+    pub fn count(&self) -> Option<u64> {
+        match self {
+            EventKind::Log | EventKind::Event(_) | EventKind::Lifecycle(_) => None,
+            EventKind::Dropped { count }
+            | EventKind::Suppressed { count }
+            | EventKind::Repeated { count } => Some(*count),
+        }
+    }
+}
+
+/// A pod's latest details, as its streams see them. Updated when the pod
+/// watcher learns more, e.g. the node and IP once a pending pod is scheduled.
+pub type MetaRx = watch::Receiver<Option<Arc<PodMeta>>>;
+
+/// Pod details attached to events when enrichment is enabled.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct PodMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replica_set: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Image per container name
+    #[serde(skip)]
+    pub images: BTreeMap<String, String>,
+}
+
+impl PodMeta {
+    pub fn image(&self, container: &str) -> Option<&str> {
+        self.images.get(container).map(String::as_str)
+    }
+}
+
+/// Splits an image reference into its name and tag (or digest).
+pub fn split_image(image: &str) -> (&str, Option<&str>) {
+    if let Some((name, digest)) = image.split_once('@') {
+        return (name, Some(digest));
+    }
+    let path_end = image.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match image[path_end..].find(':') {
+        Some(i) => (&image[..path_end + i], Some(&image[path_end + i + 1..])),
+        None => (image, None),
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -40,6 +221,42 @@ pub struct LogEvent {
 pub enum OutputMode {
     Human,
     Json,
+    Logfmt,
+    Csv,
+    Tsv,
+    OtlpJson,
+    Raw,
+}
+
+impl fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputMode::Human => write!(f, "human"),
+            OutputMode::Json => write!(f, "json"),
+            OutputMode::Logfmt => write!(f, "logfmt"),
+            OutputMode::Csv => write!(f, "csv"),
+            OutputMode::Tsv => write!(f, "tsv"),
+            OutputMode::OtlpJson => write!(f, "otlp-json"),
+            OutputMode::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(OutputMode::Human),
+            "json" => Ok(OutputMode::Json),
+            "logfmt" => Ok(OutputMode::Logfmt),
+            "csv" => Ok(OutputMode::Csv),
+            "tsv" => Ok(OutputMode::Tsv),
+            "otlp-json" => Ok(OutputMode::OtlpJson),
+            "raw" => Ok(OutputMode::Raw),
+            _ => Err(format!("unknown output format {s:?}")),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -58,6 +275,27 @@ impl fmt::Display for ColorBy {
     }
 }
 
+/// What a stream does when the merger falls behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    Block,
+    DropOldest,
+    DropNewest,
+    Sample,
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::Block => write!(f, "block"),
+            OverflowPolicy::DropOldest => write!(f, "drop-oldest"),
+            OverflowPolicy::DropNewest => write!(f, "drop-newest"),
+            OverflowPolicy::Sample => write!(f, "sample"),
+        }
+    }
+}
+
 /// Whether to emit ANSI colors
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
@@ -83,4 +321,10 @@ pub struct OutputConfig {
     pub color_by: ColorBy,
     pub color: ColorMode,
     pub no_color: bool,
+    /// Append pod metadata to the human label
+    pub label_meta: bool,
+    /// Start raw lines with `[pod/NAME/CONTAINER]`
+    pub prefix: bool,
+    /// Start raw lines with the event time
+    pub timestamps: bool,
 }
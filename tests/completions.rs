@@ -0,0 +1,161 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+fn bin() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
+}
+
+#[test]
+fn completions_prints_a_registration_script_per_shell() {
+    for (shell, marker) in [
+        ("bash", "complete -o nospace"),
+        ("zsh", "compdef"),
+        ("fish", "complete --keep-order"),
+    ] {
+        bin()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(marker).and(predicate::str::contains("COMPLETE=")));
+    }
+}
+
+#[test]
+fn completes_contexts_and_namespaces_from_the_kubeconfig() {
+    let dir = std::env::temp_dir().join(format!("kpl-complete-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let kubeconfig = dir.join("config");
+    // Nothing listens on port 1, so namespaces fall back to the contexts.
+    std::fs::write(
+        &kubeconfig,
+        r#"
+apiVersion: v1
+kind: Config
+clusters: [{name: c, cluster: {server: "https://127.0.0.1:1"}}]
+users: [{name: u, user: {}}]
+contexts:
+- {name: prod, context: {cluster: c, user: u, namespace: shop}}
+- {name: staging, context: {cluster: c, user: u}}
+current-context: prod
+"#,
+    )
+    .expect("write kubeconfig");
+
+    let complete = |args: &[&str]| {
+        let out = bin()
+            .env("COMPLETE", "fish")
+            .env("KUBECONFIG", &kubeconfig)
+            .args(["--", "kpl"])
+            .args(args)
+            .output()
+            .expect("run completer");
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    assert_eq!(complete(&["--context", "st"]).trim(), "staging");
+    assert_eq!(complete(&["-n", ""]).trim(), "shop");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn completes_selectors_and_targets_from_the_cluster() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    // A stand-in API server with pods in the shop namespace only.
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let server = format!("http://{}", listener.local_addr().expect("addr"));
+    std::thread::spawn(move || {
+        let pod = |name: &str, labels: &str| {
+            format!(r#"{{"metadata":{{"name":"{name}","labels":{{{labels}}}}}}}"#)
+        };
+        let body = format!(
+            r#"{{"apiVersion":"v1","kind":"PodList","metadata":{{}},"items":[{},{},{}]}}"#,
+            pod(
+                "web-1",
+                r#""app":"web","tier":"front","pod-template-hash":"7d4b9c""#
+            ),
+            pod("web-2", r#""app":"web","tier":"front""#),
+            pod("db-1", r#""app":"db""#),
+        );
+        for conn in listener.incoming() {
+            let mut conn = conn.expect("accept");
+            let mut reader = BufReader::new(conn.try_clone().expect("clone connection"));
+            let mut request = String::new();
+            let _ = reader.read_line(&mut request);
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                line.clear();
+            }
+            let body = if request.contains("/namespaces/shop/deployments") {
+                r#"{"apiVersion":"apps/v1","kind":"DeploymentList","metadata":{},"items":[{"metadata":{"name":"web"}},{"metadata":{"name":"worker"}}]}"#.to_string()
+            } else if request.contains("/namespaces/shop/pods") {
+                body.clone()
+            } else {
+                r#"{"apiVersion":"v1","kind":"PodList","metadata":{},"items":[]}"#.to_string()
+            };
+            let _ = write!(
+                conn,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+        }
+    });
+
+    let dir = std::env::temp_dir().join(format!("kpl-complete-l-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    let kubeconfig = dir.join("config");
+    std::fs::write(
+        &kubeconfig,
+        format!(
+            r#"
+apiVersion: v1
+kind: Config
+clusters: [{{name: c, cluster: {{server: "{server}"}}}}]
+users: [{{name: u, user: {{}}}}]
+contexts: [{{name: test, context: {{cluster: c, user: u, namespace: shop}}}}]
+current-context: test
+"#
+        ),
+    )
+    .expect("write kubeconfig");
+
+    let run = |args: &[&str]| {
+        let out = bin()
+            .env("COMPLETE", "fish")
+            .env("KUBECONFIG", &kubeconfig)
+            .args(["--", "kpl"])
+            .args(args)
+            .output()
+            .expect("run completer");
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+    let complete_in =
+        |namespace: &[&str], current: &str| run(&[namespace, &["-l", current]].concat());
+    let complete = |current: &str| complete_in(&["-n", "shop"], current);
+
+    // Rollout hashes are left out; the help says how many pods carry it.
+    assert_eq!(
+        complete("").lines().collect::<Vec<_>>(),
+        ["app=db\t1 pod", "app=web\t2 pods", "tier=front\t2 pods"]
+    );
+    // After a comma, only keys not already given.
+    assert_eq!(complete("app=web,").trim(), "app=web,tier=front\t2 pods");
+    // Without -n, the context's namespace, as kubectl would use.
+    assert_eq!(complete_in(&[], "app=d").trim(), "app=db\t1 pod");
+    assert_eq!(complete_in(&["-n", "default"], "app=d"), "");
+
+    // Workload targets: the kinds, then the workloads of one.
+    assert_eq!(run(&["d"]).lines().collect::<Vec<_>>(), ["deploy/", "ds/"]);
+    assert_eq!(
+        run(&["deploy/w"]).lines().collect::<Vec<_>>(),
+        ["deploy/web", "deploy/worker"]
+    );
+    assert_eq!(run(&["deployment/we"]).trim(), "deployment/web");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
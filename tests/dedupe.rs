@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use kpl::config::DedupeOpts;
+use kpl::merge::dedupe::Deduper;
+use kpl::types::{EventKind, LogEvent};
+
+fn line(pod: &str, message: &str) -> LogEvent {
+    LogEvent {
+        ts: time::OffsetDateTime::now_utc(),
+        namespace: "shop".to_string(),
+        pod: pod.to_string(),
+        container: "app".to_string(),
+        message: message.to_string(),
+        meta: None,
+        kind: EventKind::Log,
+    }
+}
+
+#[tokio::test]
+async fn quiet_streams_are_forgotten_once_reported() {
+    let mut dedupe = Deduper::new(DedupeOpts {
+        timeout: Duration::from_millis(50),
+        normalize: false,
+    });
+    let mut out = Vec::new();
+
+    dedupe.push(line("api-0", "ping"), &mut out);
+    dedupe.push(line("api-0", "ping"), &mut out);
+    dedupe.push(line("api-1", "pong"), &mut out);
+    assert_eq!(out.len(), 2);
+    out.clear();
+
+    tokio::time::sleep_until(dedupe.next_deadline().expect("a held-back run")).await;
+    dedupe.expire(&mut out);
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].kind, EventKind::Repeated { count: 1 });
+    out.clear();
+
+    // Both streams are quiet now; once past the timeout nothing is kept.
+    while let Some(at) = dedupe.next_deadline() {
+        tokio::time::sleep_until(at).await;
+        dedupe.expire(&mut out);
+    }
+    assert!(out.is_empty());
+
+    // A forgotten stream starts over.
+    dedupe.push(line("api-1", "pong"), &mut out);
+    assert_eq!(out.len(), 1);
+}
@@ -1,10 +1,45 @@
 use assert_cmd::prelude::*;
-use std::process::Command;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 
 fn bin() -> Command {
     Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
 }
 
+/// Like `| head -n`: reads `n` lines, closes the pipe and waits for the
+/// process. Returns the lines, how long they took, and how long the process
+/// took to exit after that (`None` if it was still running after 8s).
+fn head(cmd: &mut Command, n: usize) -> (Vec<String>, Duration, Option<Duration>) {
+    let start = Instant::now();
+    let mut child = cmd
+        .env("RUST_LOG", "off")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn kpl");
+    let lines: Vec<String> = BufReader::new(child.stdout.take().expect("piped stdout"))
+        .lines()
+        .take(n)
+        .map(|l| l.expect("utf-8 line"))
+        .collect();
+    let read = start.elapsed();
+
+    let closed = Instant::now();
+    let exited = loop {
+        if child.try_wait().expect("wait for kpl").is_some() {
+            break Some(closed.elapsed());
+        }
+        if closed.elapsed() > Duration::from_secs(8) {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    (lines, read, exited)
+}
+
 #[test]
 fn dev_smoke_human_runs_and_exits() {
     let mut cmd = bin();
@@ -41,7 +76,8 @@ fn dev_smoke_json_is_valid_ndjson_and_nonempty() {
             "1",
             "--dev-lines",
             "3",
-            "--json",
+            "-o",
+            "json",
             "--no-color",
         ])
         .assert()
@@ -64,3 +100,684 @@ fn dev_smoke_json_is_valid_ndjson_and_nonempty() {
         "expected some JSON lines, got 0. stdout was empty."
     );
 }
+
+#[test]
+fn dev_smoke_otlp_json_carries_k8s_resource_attributes() {
+    let mut cmd = bin();
+
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "2",
+            "-o",
+            "otlp-json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let first = out.lines().next().expect("expected at least one line");
+
+    let v: serde_json::Value = serde_json::from_str(first).expect("line must be valid JSON");
+    let attrs = v["resourceLogs"][0]["resource"]["attributes"]
+        .as_array()
+        .expect("resource attributes");
+    let keys: Vec<&str> = attrs.iter().filter_map(|a| a["key"].as_str()).collect();
+
+    for k in ["k8s.namespace.name", "k8s.pod.name", "k8s.container.name"] {
+        assert!(keys.contains(&k), "missing resource attribute {k} in {v}");
+    }
+    assert!(
+        v["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0]["body"]["stringValue"].is_string()
+    );
+}
+
+#[test]
+fn dev_smoke_csv_starts_with_header() {
+    let mut cmd = bin();
+
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "2",
+            "-o",
+            "csv",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let mut lines = out.lines();
+
+    assert_eq!(lines.next(), Some("ts,namespace,pod,container,message"));
+    assert!(lines.all(|l| l.split(',').count() == 5));
+}
+
+#[test]
+fn dev_smoke_json_enrichment_adds_meta() {
+    let mut cmd = bin();
+
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "1",
+            "-o",
+            "json",
+            "--show-labels",
+            "version",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        let meta = v
+            .get("meta")
+            .unwrap_or_else(|| panic!("missing meta in {v}"));
+        for k in ["node", "image", "labels"] {
+            assert!(meta.get(k).is_some(), "missing meta.{k} in {v}");
+        }
+        assert!(meta["labels"]["version"].is_string());
+    }
+}
+
+#[test]
+fn dev_smoke_output_dir_writes_one_file_per_container() {
+    let dir = std::env::temp_dir().join(format!("kpl-smoke-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut cmd = bin();
+    cmd.env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "2",
+            "-o",
+            "raw",
+            "--output-dir",
+        ])
+        .arg(&dir)
+        .assert()
+        .success();
+
+    for container in ["app", "sidecar"] {
+        let path = dir
+            .join("default/dev-pod-1")
+            .join(format!("{container}.log"));
+        let contents = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("reading {}: {e}", path.display()));
+        assert!(
+            contents.lines().all(|l| l.starts_with("log line")),
+            "unexpected contents in {}: {contents}",
+            path.display()
+        );
+        assert!(!contents.is_empty());
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn dev_smoke_file_sink_applies_its_own_format_and_filter() {
+    let path = std::env::temp_dir().join(format!("kpl-sink-{}.ndjson", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "2",
+            "-o",
+            "raw",
+            "--sink",
+            "stdout",
+            "--sink",
+        ])
+        .arg(format!(
+            "file:{};format=json;container=^app$",
+            path.display()
+        ))
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(stdout.lines().all(|l| l.starts_with("log line")));
+
+    let contents = std::fs::read_to_string(&path).expect("file sink output");
+    let mut count = 0usize;
+    for line in contents.lines() {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        assert_eq!(v["container"], "app", "filter leaked {v}");
+        count += 1;
+    }
+    assert!(count > 0, "file sink received nothing");
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn dev_smoke_overflow_accounts_for_every_line() {
+    let lines_per_container = 5000u64;
+
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "0",
+            "--dev-lines",
+            &lines_per_container.to_string(),
+            "--buffer",
+            "4",
+            "--on-overflow",
+            "drop-oldest",
+            "-o",
+            "json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    let mut delivered = 0u64;
+    let mut dropped = 0u64;
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        match v.get("kind").and_then(|k| k.as_str()) {
+            Some("dropped") => dropped += v["count"].as_u64().expect("count"),
+            None => delivered += 1,
+            Some(other) => panic!("unexpected kind {other}"),
+        }
+    }
+
+    // Two containers, started twice by the dev pod source.
+    assert_eq!(delivered + dropped, 4 * lines_per_container);
+}
+
+#[test]
+fn dev_smoke_sample_keeps_one_in_n_and_summarises_the_rest() {
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "0",
+            "--dev-lines",
+            "100",
+            "--sample",
+            "1/10",
+            "-o",
+            "json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    let mut delivered = 0u64;
+    let mut suppressed = 0u64;
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        match v.get("kind").and_then(|k| k.as_str()) {
+            Some("suppressed") => suppressed += v["count"].as_u64().expect("count"),
+            None => delivered += 1,
+            Some(other) => panic!("unexpected kind {other}"),
+        }
+    }
+
+    assert_eq!(delivered, 40);
+    assert_eq!(delivered + suppressed, 400);
+}
+
+#[test]
+fn dev_smoke_dedupe_collapses_normalized_repeats() {
+    let lines_per_container = 50u64;
+
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "0",
+            "--dev-lines",
+            &lines_per_container.to_string(),
+            "--dedupe",
+            "--dedupe-normalize",
+            "-o",
+            "json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+
+    let mut delivered = 0u64;
+    let mut repeated = 0u64;
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        match v.get("kind").and_then(|k| k.as_str()) {
+            Some("repeated") => repeated += v["count"].as_u64().expect("count"),
+            None => delivered += 1,
+            Some(other) => panic!("unexpected kind {other}"),
+        }
+    }
+
+    assert!(repeated > 0, "expected collapsed repeats:\n{out}");
+    assert!(delivered < 4 * lines_per_container);
+    assert_eq!(delivered + repeated, 4 * lines_per_container);
+}
+
+#[test]
+fn dev_smoke_redact_masks_matches_before_output() {
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "3",
+            "--redact",
+            r"line (?P<lineno>\d+)",
+            "--redact-builtin",
+            "-o",
+            "json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let mut seen = 0;
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        assert_eq!(v["message"], "log line [REDACTED:lineno]", "{line}");
+        seen += 1;
+    }
+    assert!(seen > 0, "expected output");
+}
+
+#[test]
+fn dev_smoke_profile_from_project_config_file() {
+    let dir = std::env::temp_dir().join(format!("kpl-profile-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp dir");
+    std::fs::write(
+        dir.join(".kpl.toml"),
+        r#"
+exclude-container = ["sidecar"]
+output = "logfmt"
+
+[profile.checkout]
+namespace = "shop"
+selector = "app=checkout"
+dev-rate-ms = 1
+dev-lines = 2
+"#,
+    )
+    .expect("write .kpl.toml");
+
+    let mut cmd = bin();
+    let assert = cmd
+        .current_dir(&dir)
+        .env("HOME", &dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env("RUST_LOG", "off")
+        .args(["--dev", "-p", "checkout", "-o", "json"])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let mut seen = 0;
+    for line in out.lines().filter(|l| !l.trim().is_empty()) {
+        // -o on the command line wins over the file's logfmt.
+        let v: serde_json::Value = serde_json::from_str(line).expect("valid JSON");
+        assert_eq!(v["namespace"], "shop");
+        assert_eq!(v["container"], "app");
+        seen += 1;
+    }
+    assert_eq!(seen, 4);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn dev_smoke_pods_previews_the_filtered_containers() {
+    let mut cmd = bin();
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "pods",
+            "--dev",
+            "-n",
+            "shop",
+            "-l",
+            "app=web",
+            "--exclude-container",
+            "sidecar",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    // Everything but the age, which depends on the clock.
+    let lines: Vec<Vec<&str>> = out
+        .lines()
+        .map(|l| l.split_whitespace().take(6).collect())
+        .collect();
+
+    assert_eq!(
+        lines,
+        [
+            vec!["POD", "CONTAINERS", "PHASE", "READY", "RESTARTS", "NODE"],
+            vec!["dev-pod-1", "app", "Running", "2/2", "0", "dev-node-1"],
+        ]
+    );
+}
+
+#[test]
+fn dev_smoke_kubectl_plugin_uses_kubectl_logs_defaults() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("kubectl-kpl"));
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "3",
+            "--prefix",
+        ])
+        .assert()
+        .success();
+
+    // Default container only, bare messages, and no follow: the simulated
+    // restart never happens.
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        [
+            "[pod/dev-pod-1/app] log line 1",
+            "[pod/dev-pod-1/app] log line 2",
+            "[pod/dev-pod-1/app] log line 3",
+        ]
+    );
+}
+
+#[test]
+fn dev_smoke_until_complete_drains_then_exits_with_a_summary() {
+    let mut cmd = bin();
+
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "3",
+            "-o",
+            "raw",
+            "--until-complete",
+            "--summary",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(out.matches("log line 3").count(), 2, "{out}");
+
+    let err = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(
+        err.contains("dev-pod-1/app      Completed  exit 0"),
+        "{err}"
+    );
+    assert!(
+        err.contains("dev-pod-1/sidecar  Completed  exit 0"),
+        "{err}"
+    );
+}
+
+#[test]
+fn dev_smoke_until_complete_fails_when_a_container_does() {
+    let assert = bin()
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "3",
+            "-o",
+            "raw",
+            "--until-complete",
+            "--summary",
+            "--dev-exit-code",
+            "2",
+        ])
+        .assert()
+        .code(1);
+
+    // The logs are all written before the verdict.
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(out.matches("log line 3").count(), 2, "{out}");
+
+    let err = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(
+        err.contains("dev-pod-1/app      Error      exit 2"),
+        "{err}"
+    );
+    assert!(err.contains("1 of 2 containers failed"), "{err}");
+}
+
+#[test]
+fn dev_smoke_stop_conditions_exit_with_their_own_codes() {
+    let run = |extra: &[&str], code: i32| {
+        let assert = bin()
+            .env("RUST_LOG", "off")
+            .args(["--dev", "-l", "app=web", "--dev-rate-ms", "20", "-o", "raw"])
+            .args(extra)
+            .assert()
+            .code(code);
+        String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+    };
+
+    // The matching line is the last one written.
+    let out = run(&["--until-match", "line 3$"], 0);
+    assert_eq!(out.lines().last(), Some("log line 3"), "{out}");
+    assert_eq!(out.matches("log line 3").count(), 1, "{out}");
+
+    // Every dev stream writes all of its lines without a match.
+    let out = run(&["--until-match", "never written"], 4);
+    assert!(out.contains("log line 10"), "{out}");
+
+    let out = run(&["--max-lines", "5"], 3);
+    assert_eq!(out.lines().count(), 5, "{out}");
+
+    run(&["--timeout", "200ms"], 124);
+}
+
+#[test]
+fn dev_smoke_stats_counts_each_container() {
+    let columns = |table: &str, container: &str| -> Vec<String> {
+        table
+            .lines()
+            .find(|l| l.split_whitespace().nth(1) == Some(container))
+            .unwrap_or_else(|| panic!("no {container} row in {table}"))
+            .split_whitespace()
+            .map(str::to_string)
+            .collect()
+    };
+
+    // --stats-only: the table is all there is on stdout.
+    let assert = Command::new(assert_cmd::cargo::cargo_bin!("kubectl-kpl"))
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "5",
+            "--stats-only",
+        ])
+        .assert()
+        .success();
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert!(!out.contains("log line"), "{out}");
+    assert!(
+        out.starts_with("POD        CONTAINER  LINES  BYTES  LINES/S"),
+        "{out}"
+    );
+    let app = columns(&out, "app");
+    assert_eq!(&app[..4], ["dev-pod-1", "app", "5", "50"], "{out}");
+    assert_eq!(&app[5..7], ["0", "0"], "{out}");
+
+    // --stats: the lines stay on stdout, the table goes to stderr.
+    let assert = bin()
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "5",
+            "-o",
+            "raw",
+            "--max-lines",
+            "6",
+            "--stats",
+        ])
+        .assert()
+        .code(3);
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    assert_eq!(out.lines().count(), 6, "{out}");
+    let err = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    let lines: u64 = ["app", "sidecar"]
+        .iter()
+        .map(|c| columns(&err, c)[2].parse::<u64>().expect("a count"))
+        .sum();
+    assert_eq!(lines, 6, "{err}");
+}
+
+#[test]
+fn dev_smoke_busy_pipe_is_flushed_without_a_pause() {
+    // Lines every 5ms never leave the channel idle; the flush deadline has
+    // to get them out anyway.
+    let (lines, read, exited) = head(
+        bin().args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "5",
+            "--dev-lines",
+            "1000",
+            "-o",
+            "raw",
+        ]),
+        2,
+    );
+    assert_eq!(lines, ["log line 1", "log line 1"]);
+    assert!(read < Duration::from_secs(2), "first lines took {read:?}");
+    assert!(
+        exited.is_some_and(|d| d < Duration::from_secs(2)),
+        "still running {exited:?} after the pipe closed"
+    );
+}
+
+#[test]
+fn dev_smoke_on_match_alone_does_not_outlive_a_closed_pipe() {
+    let (lines, _, exited) = head(
+        bin().args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "5",
+            "--dev-lines",
+            "1000",
+            "-o",
+            "raw",
+            "--on-match",
+            "line=true",
+        ]),
+        2,
+    );
+    assert_eq!(lines.len(), 2);
+    assert!(
+        exited.is_some_and(|d| d < Duration::from_secs(2)),
+        "still running {exited:?} after the pipe closed"
+    );
+}
+
+#[test]
+fn dev_smoke_stats_alone_does_not_outlive_a_closed_pipe() {
+    let mut cmd = bin();
+    cmd.args([
+        "--dev",
+        "-l",
+        "app=web",
+        "--dev-rate-ms",
+        "5",
+        "--dev-lines",
+        "1000",
+        "-o",
+        "raw",
+        "--stats",
+    ]);
+    let (lines, _, exited) = head(&mut cmd, 2);
+    assert_eq!(lines.len(), 2);
+    assert!(
+        exited.is_some_and(|d| d < Duration::from_secs(2)),
+        "still running {exited:?} after the pipe closed"
+    );
+}
@@ -0,0 +1,124 @@
+use assert_cmd::prelude::*;
+use clap::Parser;
+use std::collections::HashSet;
+use std::process::Command;
+
+use k8s_openapi::api::core::v1::ObjectReference;
+use k8s_openapi::api::events::v1::{Event, EventSeries};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta};
+use k8s_openapi::chrono::{TimeZone, Utc};
+
+use kpl::cli::Cli;
+use kpl::config::Config;
+use kpl::podwatch::events::{log_event, wanted};
+use kpl::types::EventKind;
+
+fn event(kind: &str, field_path: Option<&str>) -> Event {
+    Event {
+        metadata: ObjectMeta {
+            name: Some("web-1.17f".to_string()),
+            namespace: Some("shop".to_string()),
+            ..Default::default()
+        },
+        regarding: Some(ObjectReference {
+            kind: Some(kind.to_string()),
+            name: Some("web-1".to_string()),
+            namespace: Some("shop".to_string()),
+            field_path: field_path.map(str::to_string),
+            ..Default::default()
+        }),
+        type_: Some("Warning".to_string()),
+        reason: Some("BackOff".to_string()),
+        note: Some("Back-off restarting failed container".to_string()),
+        reporting_controller: Some("kubelet".to_string()),
+        event_time: Some(MicroTime(Utc.timestamp_opt(1_700_000_000, 0).unwrap())),
+        series: Some(EventSeries {
+            count: 4,
+            last_observed_time: MicroTime(Utc.timestamp_opt(1_700_000_060, 0).unwrap()),
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn events_about_pods_become_log_events() {
+    let ev = log_event(&event("Pod", Some("spec.containers{app}"))).expect("pod event");
+
+    assert_eq!((ev.namespace.as_str(), ev.pod.as_str()), ("shop", "web-1"));
+    assert_eq!(ev.container, "app");
+    assert_eq!(ev.message, "Back-off restarting failed container");
+    // The series is newer than the first occurrence.
+    assert_eq!(ev.ts.unix_timestamp(), 1_700_000_060);
+
+    let EventKind::Event(e) = &ev.kind else {
+        panic!("expected an event, got {:?}", ev.kind);
+    };
+    assert!(e.is_warning());
+    assert_eq!((e.reason.as_str(), e.count), ("BackOff", 4));
+    assert_eq!(e.reporter.as_deref(), Some("kubelet"));
+
+    let whole_pod = log_event(&event("Pod", None)).expect("pod event");
+    assert_eq!(whole_pod.container, "");
+
+    assert!(log_event(&event("ReplicaSet", None)).is_none());
+}
+
+#[test]
+fn events_are_interleaved_with_their_own_json_shape() {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("kubectl-kpl"));
+    let assert = cmd
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "--dev-rate-ms",
+            "1",
+            "--dev-lines",
+            "1",
+            "--events",
+            "-o",
+            "json",
+        ])
+        .assert()
+        .success();
+
+    let out = String::from_utf8_lossy(&assert.get_output().stdout).to_string();
+    let lines: Vec<serde_json::Value> = out
+        .lines()
+        .map(|l| serde_json::from_str(l).expect("each line must be valid JSON"))
+        .collect();
+
+    let events: Vec<&serde_json::Value> = lines.iter().filter(|v| v["kind"] == "event").collect();
+    assert_eq!(events.len(), 2, "{out}");
+    assert_eq!(events[0]["reason"], "Scheduled");
+    assert_eq!(events[0]["container"], "");
+    assert_eq!(events[1]["event_type"], "Normal");
+    assert_eq!(events[1]["reporter"], "kubelet");
+    assert_eq!(events[1]["count"], 1);
+
+    assert!(lines.iter().any(|v| v["message"] == "log line 1"));
+}
+
+#[test]
+fn only_events_about_tailed_pods_and_containers_are_kept() {
+    let cli = Cli::parse_from([
+        "kpl",
+        "-l",
+        "app=web",
+        "-c",
+        "app,sidecar",
+        "--exclude-container",
+        "sidecar",
+    ]);
+    let opts = Config::try_from(cli).expect("valid config").kube;
+    let pods: HashSet<String> = ["web-1".to_string()].into();
+
+    let ev = |field_path: Option<&str>| log_event(&event("Pod", field_path)).expect("pod event");
+    assert!(wanted(&ev(None), &pods, &opts));
+    assert!(wanted(&ev(Some("spec.containers{app}")), &pods, &opts));
+    assert!(!wanted(&ev(Some("spec.containers{sidecar}")), &pods, &opts));
+    assert!(!wanted(&ev(Some("spec.containers{init}")), &pods, &opts));
+    // A pod the watcher doesn't know, e.g. one the selector doesn't match.
+    assert!(!wanted(&ev(None), &HashSet::new(), &opts));
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use kpl::config::FileOutputOpts;
+use kpl::merge::sink::files::SplitFiles;
+use kpl::merge::sink::Sink;
+use kpl::types::{Change, ClusterEvent, EventKind, Lifecycle, LogEvent};
+
+fn line(n: u32) -> LogEvent {
+    LogEvent {
+        ts: time::OffsetDateTime::now_utc(),
+        namespace: "shop".to_string(),
+        pod: "api-0".to_string(),
+        container: "app".to_string(),
+        message: format!("line {n:04}"),
+        meta: None,
+        kind: EventKind::Log,
+    }
+}
+
+#[tokio::test]
+async fn rotated_files_are_compressed_as_the_tail_goes_on() {
+    let dir = std::env::temp_dir().join(format!("kpl-files-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut sink: Box<dyn Sink> = Box::new(
+        SplitFiles::new(
+            FileOutputOpts {
+                dir: dir.clone(),
+                template: "{pod}.log".to_string(),
+                // Ten 10-byte lines per file.
+                rotate_bytes: Some(100),
+                rotate_interval: None,
+                gzip: true,
+            },
+            None,
+        )
+        .unwrap(),
+    );
+    for n in 0..50 {
+        let ev = line(n);
+        sink.write(&ev, &ev.message).await.unwrap();
+        // Rotated names have microsecond stamps.
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    sink.close().await.unwrap();
+
+    let mut names: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 5, "{names:?}");
+    assert_eq!(names[0], "api-0.log");
+    assert!(names[1..].iter().all(|n| n.ends_with(".gz")), "{names:?}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn pod_level_lines_get_a_visible_file() {
+    let dir = std::env::temp_dir().join(format!("kpl-files-pod-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut sink: Box<dyn Sink> = Box::new(
+        SplitFiles::new(
+            FileOutputOpts {
+                dir: dir.clone(),
+                template: "{namespace}/{pod}/{container}.log".to_string(),
+                rotate_bytes: None,
+                rotate_interval: None,
+                gzip: false,
+            },
+            None,
+        )
+        .unwrap(),
+    );
+    let ev = LogEvent {
+        container: String::new(),
+        message: "Pulled image".to_string(),
+        kind: EventKind::Event(Box::new(ClusterEvent {
+            event_type: "Normal".to_string(),
+            reason: "Pulled".to_string(),
+            count: 1,
+            reporter: None,
+        })),
+        ..line(0)
+    };
+    sink.write(&ev, &ev.message).await.unwrap();
+    let ev = LogEvent {
+        container: String::new(),
+        message: "+ api-0 deleted".to_string(),
+        kind: EventKind::Lifecycle(Box::new(Lifecycle {
+            change: Change::Deleted,
+            reason: None,
+            exit_code: None,
+            restarts: None,
+        })),
+        ..line(0)
+    };
+    sink.write(&ev, &ev.message).await.unwrap();
+    sink.close().await.unwrap();
+
+    let events = std::fs::read_to_string(dir.join("shop/api-0/_pod.log")).unwrap();
+    assert_eq!(events, "Pulled image\n+ api-0 deleted\n");
+    assert!(!dir.join("shop/api-0/.log").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn interval_rotation_is_checked_on_flush() {
+    let dir = std::env::temp_dir().join(format!("kpl-files-flush-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut sink: Box<dyn Sink> = Box::new(
+        SplitFiles::new(
+            FileOutputOpts {
+                dir: dir.clone(),
+                template: "{pod}.log".to_string(),
+                rotate_bytes: None,
+                rotate_interval: Some(Duration::from_millis(20)),
+                gzip: false,
+            },
+            None,
+        )
+        .unwrap(),
+    );
+    let ev = line(0);
+    sink.write(&ev, &ev.message).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    // No more lines for this stream; the flush alone rotates it.
+    sink.flush().await.unwrap();
+
+    let names: Vec<String> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names.len(), 1, "{names:?}");
+    assert!(names[0].starts_with("api-0.log."), "{names:?}");
+
+    sink.close().await.unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn streams_past_the_open_file_cap_keep_appending() {
+    let dir = std::env::temp_dir().join(format!("kpl-files-cap-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut sink: Box<dyn Sink> = Box::new(
+        SplitFiles::new(
+            FileOutputOpts {
+                dir: dir.clone(),
+                template: "{container}.log".to_string(),
+                rotate_bytes: None,
+                rotate_interval: None,
+                gzip: false,
+            },
+            Some("# header".to_string()),
+        )
+        .unwrap(),
+    );
+    // More streams than files kept open: early ones are closed, then
+    // reopened for their second line.
+    for n in 0..2 {
+        for c in 0..300 {
+            let ev = LogEvent {
+                container: format!("c{c}"),
+                ..line(n)
+            };
+            sink.write(&ev, &ev.message).await.unwrap();
+        }
+    }
+    sink.close().await.unwrap();
+
+    for c in [0, 150, 299] {
+        let text = std::fs::read_to_string(dir.join(format!("c{c}.log"))).unwrap();
+        assert_eq!(text, "# header\nline 0000\nline 0001\n", "c{c}");
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,156 @@
+use assert_cmd::prelude::*;
+use std::ffi::OsString;
+use std::process::Command;
+use std::time::Duration;
+
+use kpl::config::{Config, HookAction, HookLimits, HookSpec};
+use kpl::settings::parse_cli_from;
+
+#[test]
+fn on_match_rules_split_at_the_first_unescaped_equals() {
+    let hook: HookSpec = r"status\=5\d\d=notify-send kpl FOO=bar"
+        .parse()
+        .expect("valid rule");
+    assert_eq!(hook.pattern.as_str(), r"status=5\d\d");
+    assert_eq!(
+        hook.action,
+        HookAction::Command("notify-send kpl FOO=bar".to_string())
+    );
+
+    let hook: HookSpec = "OOMKilled=https://hooks.example.com/kpl"
+        .parse()
+        .expect("valid rule");
+    assert_eq!(
+        hook.action,
+        HookAction::Webhook("https://hooks.example.com/kpl".to_string())
+    );
+
+    for bad in ["no-action", "=cmd", "panic=", "(unclosed=cmd"] {
+        assert!(bad.parse::<HookSpec>().is_err(), "{bad:?} should fail");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn on_match_runs_the_command_with_the_event_within_its_limits() {
+    let path = std::env::temp_dir().join(format!("kpl-hook-{}.out", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "-c",
+            "app",
+            "--dev-rate-ms",
+            "5",
+            "-o",
+            "raw",
+            "--max-lines",
+            "5",
+            "--on-match-debounce",
+            "0",
+            "--on-match-max-per-minute",
+            "2",
+            "--on-match",
+        ])
+        .arg(format!(
+            r#"line \d$=echo "$KPL_POD/$KPL_CONTAINER $KPL_MESSAGE $(cat)" >> {}"#,
+            path.display()
+        ))
+        .assert()
+        .code(3);
+
+    // The run waits for its actions before exiting.
+    let out = std::fs::read_to_string(&path).expect("the hook ran");
+    let _ = std::fs::remove_file(&path);
+
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2, "{out}");
+    let (prefix, json) = lines[0].split_once(" {").expect("env then stdin");
+    assert_eq!(prefix, "dev-pod-1/app log line 1");
+    let v: serde_json::Value = serde_json::from_str(&format!("{{{json}")).expect("valid JSON");
+    assert_eq!(v["message"], "log line 1");
+}
+
+#[test]
+fn on_match_rules_can_carry_their_own_limits() {
+    let defaults = HookLimits {
+        debounce: Duration::from_secs(5),
+        max_per_minute: 10,
+    };
+
+    let hook: HookSpec = "[debounce=1m, max-per-minute=2]OOMKilled=page-oncall"
+        .parse()
+        .expect("valid rule");
+    assert_eq!(hook.pattern.as_str(), "OOMKilled");
+    let limits = hook.limits(&defaults);
+    assert_eq!(limits.debounce, Duration::from_secs(60));
+    assert_eq!(limits.max_per_minute, 2);
+
+    // A character class is still a regex.
+    let hook: HookSpec = "[Ee]rror=notify".parse().expect("valid rule");
+    assert_eq!(hook.pattern.as_str(), "[Ee]rror");
+    assert_eq!(hook.limits(&defaults).max_per_minute, 10);
+
+    for bad in [
+        "[debounce=soon]x=cmd",
+        "[max-per-minute=0]x=cmd",
+        "[debounce=1s,retries=3]x=cmd",
+    ] {
+        assert!(bad.parse::<HookSpec>().is_err(), "{bad:?} should fail");
+    }
+}
+
+#[test]
+fn on_match_rules_load_from_the_config_file() {
+    let path = std::env::temp_dir().join(format!("kpl-hooks-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"
+on-match-debounce = "10s"
+
+[[on-match]]
+match = "status=5\\d\\d"
+run = "notify-send kpl"
+max-per-minute = 3
+
+[[on-match]]
+match = "OOMKilled"
+url = "https://hooks.example.com/kpl"
+debounce = "0s"
+"#,
+    )
+    .unwrap();
+
+    let cli = parse_cli_from(
+        ["kpl", "--config", path.to_str().unwrap(), "-l", "app=web"]
+            .map(OsString::from)
+            .to_vec(),
+    )
+    .expect("valid config file");
+    std::fs::remove_file(&path).unwrap();
+    let config = Config::try_from(cli).expect("valid config");
+
+    let [status, oom] = &config.hooks[..] else {
+        panic!("expected two rules, got {:?}", config.hooks);
+    };
+    assert_eq!(status.pattern.as_str(), r"status=5\d\d");
+    assert_eq!(
+        status.action,
+        HookAction::Command("notify-send kpl".to_string())
+    );
+    let limits = status.limits(&config.hook_limits);
+    assert_eq!(limits.debounce, Duration::from_secs(10));
+    assert_eq!(limits.max_per_minute, 3);
+
+    assert_eq!(
+        oom.action,
+        HookAction::Webhook("https://hooks.example.com/kpl".to_string())
+    );
+    let limits = oom.limits(&config.hook_limits);
+    assert_eq!(limits.debounce, Duration::ZERO);
+    assert_eq!(limits.max_per_minute, 10);
+}
@@ -0,0 +1,180 @@
+use assert_cmd::prelude::*;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+fn bin() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
+}
+
+/// Reads one HTTP/1 request and returns its body.
+fn read_request(conn: &mut TcpStream) -> String {
+    let mut reader = BufReader::new(conn.try_clone().expect("clone connection"));
+    let mut len = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).expect("request header");
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                len = value.trim().parse().expect("content length");
+            }
+        }
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).expect("request body");
+    String::from_utf8(body).expect("utf-8 body")
+}
+
+fn respond(conn: &mut TcpStream, status: &str) {
+    write!(
+        conn,
+        "HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+    )
+    .expect("write response");
+}
+
+#[test]
+fn http_sink_retries_a_server_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}/logs", listener.local_addr().expect("addr"));
+    let (bodies, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        for (i, conn) in listener.incoming().enumerate() {
+            let mut conn = conn.expect("accept");
+            let body = read_request(&mut conn);
+            respond(&mut conn, if i == 0 { "503 Unavailable" } else { "200 OK" });
+            let _ = bodies.send((i, body));
+        }
+    });
+
+    bin()
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "-c",
+            "app",
+            "--dev-rate-ms",
+            "1",
+            "-o",
+            "raw",
+            "--max-lines",
+            "3",
+            "--sink",
+            &url,
+        ])
+        .assert()
+        .code(3);
+
+    // The same batch again, after the 503.
+    let (_, first) = received
+        .recv_timeout(Duration::from_secs(1))
+        .expect("a POST");
+    let (i, second) = received
+        .recv_timeout(Duration::from_secs(1))
+        .expect("a retry");
+    assert_eq!(i, 1);
+    assert_eq!(second, first);
+    assert!(first.starts_with("log line 1\n"), "{first:?}");
+}
+
+#[test]
+fn http_sink_that_hangs_does_not_hold_up_stdout() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}/logs", listener.local_addr().expect("addr"));
+    std::thread::spawn(move || {
+        // Accept and never answer.
+        let conns: Vec<_> = listener.incoming().collect();
+        drop(conns);
+    });
+
+    let start = Instant::now();
+    let mut child = bin()
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app=web",
+            "-c",
+            "app",
+            "--dev-rate-ms",
+            "20",
+            "-o",
+            "raw",
+            "--sink",
+            "stdout",
+            "--sink",
+            &url,
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("spawn kpl");
+    let lines: Vec<String> = BufReader::new(child.stdout.take().expect("piped stdout"))
+        .lines()
+        .take(5)
+        .map(|l| l.expect("utf-8 line"))
+        .collect();
+    let took = start.elapsed();
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert_eq!(lines.last().map(String::as_str), Some("log line 5"));
+    assert!(took < Duration::from_secs(2), "stdout waited {took:?}");
+}
+
+#[tokio::test]
+async fn http_sink_batches_regardless_of_merger_flushes() {
+    use kpl::merge::sink::http::HttpSink;
+    use kpl::merge::sink::Sink;
+    use kpl::types::{EventKind, LogEvent, OutputMode};
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}/logs", listener.local_addr().expect("addr"));
+    let (bodies, received) = mpsc::channel();
+    std::thread::spawn(move || {
+        for conn in listener.incoming() {
+            let mut conn = conn.expect("accept");
+            let body = read_request(&mut conn);
+            respond(&mut conn, "200 OK");
+            let _ = bodies.send(body);
+        }
+    });
+
+    let ev = LogEvent {
+        ts: time::OffsetDateTime::now_utc(),
+        namespace: "default".to_string(),
+        pod: "web-1".to_string(),
+        container: "app".to_string(),
+        message: String::new(),
+        meta: None,
+        kind: EventKind::Log,
+    };
+    let mut sink = HttpSink::new(&url, OutputMode::Raw).expect("sink");
+    // On a terminal the merger flushes after every write.
+    for n in 0..20 {
+        sink.write(&ev, &format!("line {n}")).await.unwrap();
+        sink.flush().await.unwrap();
+    }
+
+    // One POST once the batch has lingered, without waiting for the end.
+    let body = tokio::task::spawn_blocking(move || {
+        let body = received
+            .recv_timeout(Duration::from_secs(3))
+            .expect("a POST");
+        assert!(received.recv_timeout(Duration::from_millis(200)).is_err());
+        body
+    })
+    .await
+    .unwrap();
+    assert_eq!(body.lines().count(), 20, "{body:?}");
+
+    Box::new(sink).close().await.unwrap();
+}
@@ -0,0 +1,124 @@
+use clap::Parser;
+use k8s_openapi::api::core::v1::{
+    ContainerState, ContainerStateTerminated, ContainerStatus, Pod, PodCondition, PodStatus,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time};
+use k8s_openapi::chrono::{TimeZone, Utc};
+
+use kpl::cli::Cli;
+use kpl::config::Config;
+use kpl::merge::format::json;
+use kpl::podwatch::lifecycle::Tracker;
+use kpl::types::{LogEvent, PodKey};
+
+fn pod(name: &str, ready: bool, restarts: i32, last_exit: Option<(&str, i32)>) -> Pod {
+    Pod {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some("shop".to_string()),
+            uid: Some(format!("{name}-uid")),
+            ..Default::default()
+        },
+        status: Some(PodStatus {
+            conditions: Some(vec![PodCondition {
+                type_: "Ready".to_string(),
+                status: if ready { "True" } else { "False" }.to_string(),
+                ..Default::default()
+            }]),
+            container_statuses: Some(vec![ContainerStatus {
+                name: "app".to_string(),
+                restart_count: restarts,
+                last_state: last_exit.map(|(reason, code)| ContainerState {
+                    terminated: Some(ContainerStateTerminated {
+                        reason: Some(reason.to_string()),
+                        exit_code: code,
+                        container_id: Some(format!("containerd://{restarts}")),
+                        finished_at: Some(Time(Utc.timestamp_opt(1_700_000_000, 0).unwrap())),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn messages(lines: Vec<LogEvent>) -> Vec<String> {
+    lines.into_iter().map(|ev| ev.message).collect()
+}
+
+#[test]
+fn lifecycle_announces_each_change_once() {
+    let mut tracker = Tracker::new();
+
+    // Pods already running when the tail starts aren't news.
+    tracker.baseline(&[pod("web-old", true, 0, None)]);
+    assert!(tracker.applied(&pod("web-old", true, 0, None)).is_empty());
+
+    assert_eq!(
+        messages(tracker.applied(&pod("checkout-7f9", false, 0, None))),
+        ["+ checkout-7f9 started"]
+    );
+    assert_eq!(
+        messages(tracker.applied(&pod("checkout-7f9", true, 0, None))),
+        ["+ checkout-7f9 ready"]
+    );
+
+    let crashed = pod("checkout-7f9", false, 1, Some(("OOMKilled", 137)));
+    let lines = tracker.applied(&crashed);
+    assert_eq!(
+        lines
+            .iter()
+            .map(|ev| ev.container.as_str())
+            .collect::<Vec<_>>(),
+        ["app", "app"]
+    );
+    assert_eq!(
+        messages(lines),
+        [
+            "- checkout-7f9/app OOMKilled (exit 137)",
+            "↻ checkout-7f9/app restarted (restart 1)"
+        ]
+    );
+    // The same status again changes nothing.
+    assert!(tracker.applied(&crashed).is_empty());
+
+    let key = PodKey {
+        namespace: "shop".to_string(),
+        name: "checkout-7f9".to_string(),
+        uid: "checkout-7f9-uid".to_string(),
+    };
+    assert_eq!(messages(tracker.deleted(&key)), ["- checkout-7f9 deleted"]);
+}
+
+#[test]
+fn lifecycle_lines_have_their_own_json_shape() {
+    let mut tracker = Tracker::new();
+    tracker.baseline(&[pod("web-1", true, 0, None)]);
+    let lines = tracker.applied(&pod("web-1", true, 1, Some(("Error", 2))));
+
+    let v: serde_json::Value = serde_json::from_str(&json::format(&lines[0])).expect("valid JSON");
+    assert_eq!(v["kind"], "lifecycle");
+    assert_eq!(v["change"], "terminated");
+    assert_eq!(v["reason"], "Error");
+    assert_eq!(v["exit_code"], 2);
+    assert_eq!(v["namespace"], "shop");
+
+    let v: serde_json::Value = serde_json::from_str(&json::format(&lines[1])).expect("valid JSON");
+    assert_eq!(v["change"], "restarted");
+    assert_eq!(v["restarts"], 1);
+}
+
+#[test]
+fn lifecycle_is_on_while_following_unless_opted_out() {
+    let config = |args: &[&str]| {
+        let cli = Cli::parse_from(["kpl", "-l", "app=web"].iter().chain(args));
+        Config::try_from(cli).expect("valid config")
+    };
+
+    assert!(config(&[]).kube.lifecycle);
+    assert!(!config(&["--no-lifecycle"]).kube.lifecycle);
+}
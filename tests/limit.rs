@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+use kpl::config::LimitOpts;
+use kpl::stream::channel::log_channel;
+use kpl::stream::limit::LimitedTx;
+use kpl::types::{EventKind, LogEvent, OverflowPolicy, PodKey, StreamKey};
+
+fn line(n: u32) -> LogEvent {
+    LogEvent {
+        ts: time::OffsetDateTime::now_utc(),
+        namespace: "shop".to_string(),
+        pod: "api-0".to_string(),
+        container: "app".to_string(),
+        message: format!("line {n}"),
+        meta: None,
+        kind: EventKind::Log,
+    }
+}
+
+#[tokio::test]
+async fn suppressed_lines_are_reported_after_the_stream_goes_quiet() {
+    let (log_tx, mut log_rx) = log_channel(64, OverflowPolicy::Block);
+    let key = StreamKey {
+        pod: PodKey {
+            namespace: "shop".to_string(),
+            name: "api-0".to_string(),
+            uid: "api-0-uid".to_string(),
+        },
+        container: "app".to_string(),
+    };
+    let (_meta_tx, meta) = watch::channel(None);
+    let mut tx = LimitedTx::new(
+        log_tx,
+        key,
+        meta,
+        &LimitOpts {
+            max_lines_per_sec: Some(1),
+            sample_every: None,
+        },
+    );
+
+    for n in 0..5 {
+        tx.send(line(n)).await.unwrap();
+    }
+    assert_eq!(log_rx.recv().await.unwrap().message, "line 0");
+
+    // No further line arrives to carry the summary; the timer sends it.
+    tokio::time::timeout(Duration::from_secs(3), tx.summary_due())
+        .await
+        .expect("summary within the interval")
+        .unwrap();
+    let summary = log_rx.recv().await.unwrap();
+    assert_eq!(summary.kind, EventKind::Suppressed { count: 4 });
+
+    // Nothing left to report.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(1500), tx.summary_due())
+            .await
+            .is_err()
+    );
+}
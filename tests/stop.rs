@@ -0,0 +1,77 @@
+use regex::Regex;
+use time::OffsetDateTime;
+
+use kpl::config::StopOpts;
+use kpl::merge::stop::StopAt;
+use kpl::shutdown::ShutdownReason;
+use kpl::types::{Change, EventKind, Lifecycle, LogEvent};
+
+fn event(message: &str, kind: EventKind) -> LogEvent {
+    LogEvent {
+        ts: OffsetDateTime::UNIX_EPOCH,
+        namespace: "shop".to_string(),
+        pod: "web-1".to_string(),
+        container: "app".to_string(),
+        message: message.to_string(),
+        meta: None,
+        kind,
+    }
+}
+
+fn log(message: &str) -> LogEvent {
+    event(message, EventKind::Log)
+}
+
+fn started() -> LogEvent {
+    event(
+        "+ web-1 started",
+        EventKind::Lifecycle(Box::new(Lifecycle {
+            change: Change::Started,
+            reason: None,
+            exit_code: None,
+            restarts: None,
+        })),
+    )
+}
+
+fn stop_at(until_match: Option<&str>, max_lines: Option<u64>) -> StopAt {
+    StopAt::new(&StopOpts {
+        until_match: until_match.map(|re| Regex::new(re).expect("valid regex")),
+        timeout: None,
+        max_lines,
+    })
+    .expect("a stop condition")
+}
+
+#[test]
+fn until_match_ignores_kpl_own_lines() {
+    let mut stop = stop_at(Some("started"), None);
+
+    assert_eq!(stop.after(&started()), None);
+    assert_eq!(
+        stop.after(&event("started", EventKind::Dropped { count: 2 })),
+        None
+    );
+    assert_eq!(stop.after(&log("listening")), None);
+    assert_eq!(
+        stop.after(&log("server started")),
+        Some(ShutdownReason::Matched)
+    );
+}
+
+#[test]
+fn max_lines_counts_log_lines_and_their_repeats() {
+    let mut stop = stop_at(None, Some(4));
+
+    assert_eq!(stop.after(&started()), None);
+    assert_eq!(stop.after(&log("a")), None);
+    assert_eq!(
+        stop.after(&event("", EventKind::Suppressed { count: 10 })),
+        None
+    );
+    assert_eq!(stop.after(&log("b")), None);
+    assert_eq!(
+        stop.after(&event("b", EventKind::Repeated { count: 2 })),
+        Some(ShutdownReason::MaxLines)
+    );
+}
@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use kpl::config::LimitOpts;
+use kpl::stream::channel::log_channel;
+use kpl::stream::status::StatusBoard;
+use kpl::stream::supervisor::{StreamBackend, StreamSupervisor};
+use kpl::types::{EventKind, OverflowPolicy, PodCommand, PodKey, PodMeta};
+
+fn start(node: Option<&str>) -> PodCommand {
+    PodCommand::StartPod {
+        pod: PodKey {
+            namespace: "shop".to_string(),
+            name: "api-0".to_string(),
+            uid: "api-0-uid".to_string(),
+        },
+        containers: vec!["app".to_string()],
+        meta: Some(Arc::new(PodMeta {
+            node: node.map(str::to_string),
+            ..Default::default()
+        })),
+    }
+}
+
+#[tokio::test]
+async fn running_stream_picks_up_new_pod_meta() {
+    let (log_tx, mut log_rx) = log_channel(64, OverflowPolicy::Block);
+    let (fatal_tx, _fatal_rx) = mpsc::channel(1);
+    let mut supervisor = StreamSupervisor::new(
+        log_tx,
+        fatal_tx,
+        StreamBackend::Dev {
+            rate_ms: 10,
+            max_lines: None,
+        },
+        LimitOpts::default(),
+        StatusBoard::default(),
+        CancellationToken::new(),
+    );
+
+    // Scheduled pods are first seen without a node.
+    supervisor.handle_command(start(None)).await;
+    let first = log_rx.recv().await.expect("a line");
+    assert_eq!(first.meta.as_ref().unwrap().node, None);
+
+    supervisor.handle_command(start(Some("node-a"))).await;
+    let refreshed = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let ev = log_rx.recv().await.expect("a line");
+            if !matches!(ev.kind, EventKind::Log) {
+                continue;
+            }
+            if let Some(node) = ev.meta.as_ref().and_then(|m| m.node.clone()) {
+                return node;
+            }
+        }
+    })
+    .await
+    .expect("the stream never saw the new node");
+    assert_eq!(refreshed, "node-a");
+
+    supervisor.shutdown_all();
+}
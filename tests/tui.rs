@@ -0,0 +1,182 @@
+use assert_cmd::prelude::*;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use predicates::prelude::*;
+use std::process::Command;
+
+use kpl::stream::status::{StreamState, StreamTable, WatcherState};
+use kpl::tui::app::{App, Entry};
+use kpl::types::{EventKind, LogEvent, PodKey, StreamKey};
+
+fn bin() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
+}
+
+fn event(pod: &str, container: &str, message: &str) -> LogEvent {
+    LogEvent {
+        ts: time::OffsetDateTime::now_utc(),
+        namespace: "default".to_string(),
+        pod: pod.to_string(),
+        container: container.to_string(),
+        message: message.to_string(),
+        meta: None,
+        kind: EventKind::Log,
+    }
+}
+
+fn press(app: &mut App, code: KeyCode) {
+    app.on_key(KeyEvent::new(code, KeyModifiers::NONE));
+}
+
+#[test]
+fn tui_pause_filter_and_hide_shape_the_view() {
+    let mut app = App::new(3);
+    for i in 0..4 {
+        app.push(event("web-1", "app", &format!("line {i}")));
+    }
+    // Scroll-back is bounded.
+    assert_eq!(app.total(), 3);
+
+    press(&mut app, KeyCode::Char(' '));
+    app.push(event("web-1", "app", "while paused"));
+    assert!(!app.following());
+    assert_eq!(app.unseen, 1);
+    press(&mut app, KeyCode::Char('G'));
+    assert!(app.following());
+
+    press(&mut app, KeyCode::Char('/'));
+    for c in "paused".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    press(&mut app, KeyCode::Enter);
+    let shown: Vec<&str> = app.visible().iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(shown, ["while paused"]);
+
+    // Clear the filter, then switch the container off in the panel.
+    press(&mut app, KeyCode::Char('/'));
+    for _ in 0.."paused".len() {
+        press(&mut app, KeyCode::Backspace);
+    }
+    press(&mut app, KeyCode::Enter);
+    app.push(event("web-2", "app", "other pod"));
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Char(' '));
+    let shown: Vec<&str> = app.visible().iter().map(|e| e.pod.as_str()).collect();
+    assert_eq!(shown, ["web-2"]);
+}
+
+#[test]
+fn tui_panel_can_hide_lines_about_the_whole_pod() {
+    let mut app = App::new(100);
+    app.push(event("web-1", "app", "from the container"));
+    app.push(event("web-1", "", "Pulled image"));
+    assert_eq!(
+        app.entries(),
+        [
+            Entry::Pod("web-1".to_string()),
+            Entry::Container("web-1".to_string(), String::new()),
+            Entry::Container("web-1".to_string(), "app".to_string()),
+        ]
+    );
+
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Char(' '));
+    let shown: Vec<&str> = app.visible().iter().map(|e| e.message.as_str()).collect();
+    assert_eq!(shown, ["from the container"]);
+
+    // The pod's own entry still counts as shown, through its container.
+    assert!(app.is_shown(&Entry::Pod("web-1".to_string())));
+    press(&mut app, KeyCode::Up);
+    press(&mut app, KeyCode::Char(' '));
+    assert!(app.visible().is_empty());
+}
+
+#[test]
+fn tui_status_bar_reports_pod_watch_retries() {
+    let mut app = App::new(100);
+    assert_eq!(app.watcher_notice(), None);
+
+    app.watcher = WatcherState::Retrying {
+        failures: 3,
+        error: "connection refused".to_string(),
+    };
+    assert_eq!(
+        app.watcher_notice().as_deref(),
+        Some("pod watch retrying (3 failed): connection refused")
+    );
+}
+
+fn key(pod: &str, container: &str) -> StreamKey {
+    StreamKey {
+        pod: PodKey {
+            namespace: "default".to_string(),
+            name: pod.to_string(),
+            uid: format!("{pod}-uid"),
+        },
+        container: container.to_string(),
+    }
+}
+
+#[test]
+fn tui_split_view_has_a_pane_per_stream_that_pauses_together() {
+    let mut app = App::new(100);
+    let streams: StreamTable = [
+        (key("web-2", "app"), StreamState::Attached),
+        (key("web-1", "app"), StreamState::Pending),
+    ]
+    .into_iter()
+    .collect();
+    app.set_streams(streams);
+
+    press(&mut app, KeyCode::Char('s'));
+    assert!(app.split);
+    let panes: Vec<&str> = app.panes().iter().map(|k| k.pod.name.as_str()).collect();
+    assert_eq!(panes, ["web-1", "web-2"]);
+
+    app.push(event("web-1", "app", "a1"));
+    app.push(event("web-2", "app", "b1"));
+    press(&mut app, KeyCode::Char(' '));
+    app.push(event("web-1", "app", "a2"));
+
+    let web1 = key("web-1", "app");
+    let shown: Vec<&str> = app
+        .pane_lines(&web1, 10)
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect();
+    assert_eq!(shown, ["a1"]);
+
+    press(&mut app, KeyCode::Char('G'));
+    let shown: Vec<&str> = app
+        .pane_lines(&web1, 1)
+        .iter()
+        .map(|e| e.message.as_str())
+        .collect();
+    assert_eq!(shown, ["a2"]);
+
+    // A stopped stream loses its pane.
+    app.set_streams([(key("web-2", "app"), StreamState::Attached)].into());
+    assert_eq!(app.panes().len(), 1);
+
+    press(&mut app, KeyCode::Char('s'));
+    assert!(!app.split);
+}
+
+#[test]
+fn tui_needs_a_terminal_and_owns_stdout() {
+    bin()
+        .env("RUST_LOG", "off")
+        .args(["--dev", "-l", "app=web", "--tui"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--tui needs a terminal"));
+
+    bin()
+        .args(["--dev", "-l", "app=web", "--tui", "--sink", "stdout"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "--tui conflicts with --sink stdout",
+        ));
+}
@@ -0,0 +1,144 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+use kpl::kube::selector::{LabelSelector, Op};
+use kpl::kube::target::{selector_string, Kind, Target};
+
+fn bin() -> Command {
+    Command::new(assert_cmd::cargo::cargo_bin!("kpl"))
+}
+
+#[test]
+fn selector_accepts_equality_set_and_existence_requirements() {
+    let sel: LabelSelector = "app=web, tier!=db,env in (prod, staging),!canary,example.com/team"
+        .parse()
+        .expect("valid selector");
+
+    let ops: Vec<Op> = sel.requirements.iter().map(|r| r.op).collect();
+    assert_eq!(
+        ops,
+        [
+            Op::Equals,
+            Op::NotEquals,
+            Op::In,
+            Op::DoesNotExist,
+            Op::Exists
+        ]
+    );
+    assert_eq!(sel.requirements[2].values, ["prod", "staging"]);
+    assert_eq!(sel.requirements[4].key, "example.com/team");
+}
+
+#[test]
+fn selector_rejects_malformed_input() {
+    for bad in [
+        "app=web,",
+        "app in (a,b",
+        "app in ()",
+        "app=-web",
+        "-app=web",
+        "Example.com/app=web",
+        "app ~ web",
+        "app inx (a)",
+    ] {
+        assert!(bad.parse::<LabelSelector>().is_err(), "{bad:?} should fail");
+    }
+}
+
+#[test]
+fn workload_targets_parse_like_kubectl() {
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{
+        LabelSelector as WorkloadSelector, LabelSelectorRequirement,
+    };
+
+    let target: Target = "deployments/web".parse().expect("valid target");
+    assert_eq!(target.kind, Kind::Deployment);
+    assert_eq!(target.to_string(), "deploy/web");
+    assert_eq!(
+        "STS/db".parse::<Target>().map(|t| t.kind),
+        Ok(Kind::StatefulSet)
+    );
+    for bad in ["web", "deploy/", "pod/web", "deploy/a/b"] {
+        assert!(bad.parse::<Target>().is_err(), "{bad:?} should fail");
+    }
+
+    // What the workload selects, in -l syntax the watcher accepts.
+    let selector = selector_string(&WorkloadSelector {
+        match_labels: Some([("app".to_string(), "web".to_string())].into()),
+        match_expressions: Some(vec![LabelSelectorRequirement {
+            key: "env".to_string(),
+            operator: "In".to_string(),
+            values: Some(vec!["prod".to_string(), "staging".to_string()]),
+        }]),
+    });
+    assert_eq!(selector, "app=web,env in (prod,staging)");
+    assert!(selector.parse::<LabelSelector>().is_ok());
+
+    bin()
+        .args(["--dev", "bogus/web"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "target \"bogus/web\": unknown kind",
+        ));
+}
+
+#[test]
+fn every_problem_is_reported_before_connecting() {
+    bin()
+        .env("RUST_LOG", "off")
+        .args([
+            "--dev",
+            "-l",
+            "app in (web",
+            "-n",
+            "Shop",
+            "--dev-rate-ms",
+            "0",
+            "--dev-lines",
+            "0",
+            "-o",
+            "json",
+            "--color",
+            "always",
+            "--redact",
+            "(",
+            "--rotate-size",
+            "12X",
+            "--sample",
+            "1/0",
+        ])
+        .assert()
+        .code(2)
+        .stderr(
+            predicate::str::contains("--selector")
+                .and(predicate::str::contains("--namespace \"Shop\""))
+                .and(predicate::str::contains("--dev-rate-ms"))
+                .and(predicate::str::contains("--color always"))
+                .and(predicate::str::contains("--redact \"(\""))
+                .and(predicate::str::contains(
+                    "--rotate-size \"12X\": unknown unit",
+                ))
+                .and(predicate::str::contains("--sample \"1/0\"")),
+        );
+}
+
+#[test]
+fn flags_can_come_from_kpl_variables() {
+    bin()
+        .env("RUST_LOG", "off")
+        .env("KPL_SUMMARY", "true")
+        .env("KPL_SAMPLE", "1/0")
+        // Repeatable flags take one value per line.
+        .env("KPL_REDACT", "token=\\S+\n(")
+        .args(["--dev", "-l", "app=web"])
+        .assert()
+        .code(2)
+        .stderr(
+            predicate::str::contains("--summary requires --until-complete")
+                .and(predicate::str::contains("--sample \"1/0\""))
+                .and(predicate::str::contains("--redact \"(\""))
+                .and(predicate::str::contains("token").not()),
+        );
+}
@@ -0,0 +1,463 @@
+use clap::Parser;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::StreamExt;
+use k8s_openapi::api::batch::v1::{Job, JobCondition, JobStatus};
+use k8s_openapi::api::core::v1::{
+    Container, ContainerState, ContainerStateTerminated, ContainerStatus, Pod, PodCondition,
+    PodSpec, PodStatus,
+};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+use kube::core::ErrorResponse;
+use kube_runtime::watcher::{Error, Event};
+use tokio::sync::mpsc;
+
+use kpl::cli::Cli;
+use kpl::config::{Config, KubeLogOpts};
+use kpl::podwatch::list::pod_row;
+use kpl::podwatch::watcher::{classify, Failure, JobLookup, PodWatch};
+use kpl::stream::status::{StatusBoard, WatcherState};
+use kpl::types::PodCommand;
+
+fn pod(name: &str) -> Pod {
+    Pod {
+        metadata: ObjectMeta {
+            name: Some(name.to_string()),
+            namespace: Some("shop".to_string()),
+            uid: Some(format!("{name}-uid")),
+            ..Default::default()
+        },
+        spec: Some(PodSpec {
+            containers: vec![Container {
+                name: "app".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn ready(name: &str, ready: bool) -> Pod {
+    Pod {
+        status: Some(PodStatus {
+            conditions: Some(vec![PodCondition {
+                type_: "Ready".to_string(),
+                status: if ready { "True" } else { "False" }.to_string(),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..pod(name)
+    }
+}
+
+fn finished(name: &str, phase: &str, exit_code: i32) -> Pod {
+    Pod {
+        status: Some(PodStatus {
+            phase: Some(phase.to_string()),
+            container_statuses: Some(vec![ContainerStatus {
+                name: "app".to_string(),
+                state: Some(ContainerState {
+                    terminated: Some(ContainerStateTerminated {
+                        exit_code,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }),
+        ..pod(name)
+    }
+}
+
+fn pod_watch(args: &[&str]) -> (PodWatch, mpsc::Receiver<PodCommand>) {
+    let cli = Cli::parse_from(["kpl", "-n", "shop", "-l", "app=web"].iter().chain(args));
+    let config = Config::try_from(cli).expect("valid config");
+    let (tx, rx) = mpsc::channel(64);
+    let watch = PodWatch::new(
+        config.namespace.expect("-n shop"),
+        config.selector,
+        config.kube,
+        config.enrich,
+        tx,
+    );
+    (watch, rx)
+}
+
+fn started(rx: &mut mpsc::Receiver<PodCommand>) -> Vec<String> {
+    let mut names = Vec::new();
+    while let Ok(cmd) = rx.try_recv() {
+        if let PodCommand::StartPod { pod, .. } = cmd {
+            names.push(pod.name);
+        }
+    }
+    names
+}
+
+#[tokio::test]
+async fn relist_stops_pods_deleted_while_the_watch_was_down() {
+    let (watch, mut rx) = pod_watch(&[]);
+
+    // web-b is deleted while the watch is disconnected, so the only sign of
+    // it is its absence from the next listing.
+    let events = vec![
+        Ok(Event::Restarted(vec![pod("web-a"), pod("web-b")])),
+        Ok(Event::Applied(pod("web-c"))),
+        Ok(Event::Restarted(vec![pod("web-a"), pod("web-c")])),
+    ];
+    watch
+        .run(futures::stream::iter(events))
+        .await
+        .expect("watch ends cleanly");
+
+    let mut started = Vec::new();
+    let mut stopped = Vec::new();
+    let mut notes = Vec::new();
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            PodCommand::StartPod { pod, .. } => started.push(pod.name),
+            PodCommand::StopPod { pod } => stopped.push(pod.name),
+            PodCommand::Annotate(ev) => notes.push(ev.message),
+        }
+    }
+
+    assert_eq!(stopped, ["web-b"]);
+    assert!(started.contains(&"web-c".to_string()), "{started:?}");
+    assert!(notes.contains(&"- web-b deleted".to_string()), "{notes:?}");
+}
+
+fn api_error(code: u16) -> Error {
+    Error::WatchError(ErrorResponse {
+        status: "Failure".to_string(),
+        message: format!("status {code}"),
+        reason: String::new(),
+        code,
+    })
+}
+
+#[tokio::test]
+async fn watch_errors_are_retried_unless_access_is_denied() {
+    assert_eq!(classify(&api_error(401)), Failure::Fatal);
+    assert_eq!(classify(&api_error(410)), Failure::Expired);
+    assert_eq!(classify(&api_error(503)), Failure::Transient);
+
+    let (watch, mut rx) = pod_watch(&[]);
+
+    let events = vec![
+        Err(api_error(503)),
+        Ok(Event::Restarted(vec![pod("web-a")])),
+        Err(api_error(410)),
+        Ok(Event::Restarted(vec![pod("web-a")])),
+        Err(api_error(403)),
+        // Never reached: the watch gave up.
+        Ok(Event::Applied(pod("web-b"))),
+    ];
+    let res = watch.run(futures::stream::iter(events)).await;
+    assert!(res.is_err(), "403 must end the watch");
+
+    assert_eq!(started(&mut rx), ["web-a", "web-a"]);
+}
+
+#[tokio::test]
+async fn wait_for_ready_attaches_once_the_pod_is_ready() {
+    let (watch, mut rx) = pod_watch(&["--wait-for-ready"]);
+
+    let events = vec![
+        Ok(Event::Restarted(vec![ready("web-a", false)])),
+        Ok(Event::Applied(ready("web-a", true))),
+        // Attached pods stay attached when a probe fails.
+        Ok(Event::Applied(ready("web-a", false))),
+    ];
+    watch
+        .run(futures::stream::iter(events))
+        .await
+        .expect("watch ends cleanly");
+
+    assert_eq!(started(&mut rx), ["web-a", "web-a"]);
+}
+
+#[test]
+fn pods_listing_applies_the_same_readiness_gate() {
+    let opts = |args: &[&str]| {
+        let cli = Cli::parse_from(["kpl", "-n", "shop", "-l", "app=web"].iter().chain(args));
+        Config::try_from(cli).expect("valid config").kube
+    };
+    let listed = |opts: &KubeLogOpts| -> Vec<String> {
+        [
+            ready("web-a", true),
+            ready("web-b", false),
+            finished("migrate-a", "Succeeded", 0),
+        ]
+        .iter()
+        .filter_map(|pod| pod_row(pod, opts, 0))
+        .map(|row| row.name)
+        .collect()
+    };
+
+    assert_eq!(listed(&opts(&[])), ["web-a", "web-b", "migrate-a"]);
+    assert_eq!(listed(&opts(&["--wait-for-ready"])), ["web-a", "migrate-a"]);
+}
+
+#[tokio::test]
+async fn exit_if_no_pods_after_gives_up_on_an_empty_selection() {
+    let (watch, _rx) = pod_watch(&["--exit-if-no-pods-after", "50ms"]);
+
+    let events =
+        futures::stream::iter(vec![Ok(Event::Restarted(vec![]))]).chain(futures::stream::pending());
+    let res = tokio::time::timeout(Duration::from_secs(5), watch.run(events))
+        .await
+        .expect("gives up on its own");
+
+    let err = res.expect_err("no pods is an error").to_string();
+    assert!(
+        err.contains("no pods matching app=web in shop after 50ms"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn until_complete_ends_once_every_pod_has_finished() {
+    let (watch, mut rx) = pod_watch(&["--until-complete"]);
+
+    let events = vec![
+        Ok(Event::Restarted(vec![pod("migrate-a"), pod("migrate-b")])),
+        Ok(Event::Applied(finished("migrate-a", "Succeeded", 0))),
+        Ok(Event::Applied(finished("migrate-b", "Failed", 3))),
+        // Never reached: everything already finished.
+        Ok(Event::Applied(pod("migrate-c"))),
+    ];
+    let outcome = watch
+        .run(futures::stream::iter(events))
+        .await
+        .expect("watch ends cleanly")
+        .expect("the pods completed");
+
+    assert_eq!(outcome.failed(), 1);
+    assert_eq!(
+        outcome.to_string(),
+        "migrate-a/app  Completed  exit 0\nmigrate-b/app  Error      exit 3\n"
+    );
+    assert!(!started(&mut rx).contains(&"migrate-c".to_string()));
+}
+
+fn job_pod(pod: Pod, job: &str) -> Pod {
+    let mut pod = pod;
+    pod.metadata.owner_references = Some(vec![OwnerReference {
+        api_version: "batch/v1".to_string(),
+        kind: "Job".to_string(),
+        name: job.to_string(),
+        uid: format!("{job}-uid"),
+        controller: Some(true),
+        ..Default::default()
+    }]);
+    pod
+}
+
+/// Answers with each condition in turn, then the last one forever.
+struct Jobs(std::sync::Mutex<Vec<Option<&'static str>>>);
+
+impl JobLookup for Jobs {
+    fn job<'a>(&'a self, name: &'a str) -> BoxFuture<'a, kube::Result<Job>> {
+        let mut states = self.0.lock().unwrap();
+        let state = if states.len() > 1 {
+            states.remove(0)
+        } else {
+            states[0]
+        };
+        let job = Job {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            status: Some(JobStatus {
+                conditions: state.map(|kind| {
+                    vec![JobCondition {
+                        type_: kind.to_string(),
+                        status: "True".to_string(),
+                        ..Default::default()
+                    }]
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        Box::pin(std::future::ready(Ok(job)))
+    }
+}
+
+#[tokio::test]
+async fn until_complete_waits_for_a_job_to_replace_a_failed_pod() {
+    let (watch, _rx) = pod_watch(&["--until-complete"]);
+    // Still running when the first pod fails, complete after the retry.
+    let watch = watch.with_jobs(Jobs(std::sync::Mutex::new(vec![None, Some("Complete")])));
+
+    let events = vec![
+        Ok(Event::Restarted(vec![job_pod(pod("migrate-a"), "migrate")])),
+        Ok(Event::Applied(job_pod(
+            finished("migrate-a", "Failed", 1),
+            "migrate",
+        ))),
+        Ok(Event::Applied(job_pod(pod("migrate-b"), "migrate"))),
+        Ok(Event::Applied(job_pod(
+            finished("migrate-b", "Succeeded", 0),
+            "migrate",
+        ))),
+    ];
+    let outcome = watch
+        .run(futures::stream::iter(events))
+        .await
+        .expect("watch ends cleanly")
+        .expect("the job completed");
+
+    // The attempt the Job replaced doesn't fail the run.
+    assert_eq!(outcome.failed(), 0);
+    assert_eq!(outcome.to_string(), "migrate-b/app  Completed  exit 0\n");
+}
+
+#[tokio::test]
+async fn until_complete_fails_once_the_job_gives_up() {
+    let (watch, _rx) = pod_watch(&["--until-complete"]);
+    let watch = watch.with_jobs(Jobs(std::sync::Mutex::new(vec![None, Some("Failed")])));
+
+    // The Job only reports Failed after the pod did; the watcher asks again.
+    let events = futures::stream::iter(vec![
+        Ok(Event::Restarted(vec![job_pod(pod("migrate-a"), "migrate")])),
+        Ok(Event::Applied(job_pod(
+            finished("migrate-a", "Failed", 1),
+            "migrate",
+        ))),
+    ])
+    .chain(futures::stream::pending());
+    let outcome = tokio::time::timeout(Duration::from_secs(15), watch.run(events))
+        .await
+        .expect("asks the job again")
+        .expect("watch ends cleanly")
+        .expect("the job failed");
+
+    assert_eq!(outcome.failed(), 1);
+}
+
+#[tokio::test]
+async fn watch_retries_show_on_the_status_board() {
+    let status = StatusBoard::default();
+    let mut state = status.subscribe_watcher();
+    let (watch, _rx) = pod_watch(&[]);
+    let (events, stream) = futures::channel::mpsc::unbounded();
+    let task = tokio::spawn(watch.with_status(status).run(stream));
+
+    events.unbounded_send(Err(api_error(503))).unwrap();
+    events.unbounded_send(Err(api_error(503))).unwrap();
+    let retrying = state
+        .wait_for(|s| matches!(s, WatcherState::Retrying { failures: 2, .. }))
+        .await
+        .unwrap()
+        .clone();
+    let WatcherState::Retrying { error, .. } = retrying else {
+        unreachable!()
+    };
+    assert!(error.contains("status 503"), "{error}");
+
+    events
+        .unbounded_send(Ok(Event::Restarted(vec![pod("web-a")])))
+        .unwrap();
+    state
+        .wait_for(|s| *s == WatcherState::Watching)
+        .await
+        .unwrap();
+
+    drop(events);
+    task.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn known_pods_are_published_once_listed() {
+    let status = StatusBoard::default();
+    let pods = status.subscribe_pods();
+    let (watch, _rx) = pod_watch(&[]);
+    let (events, stream) = futures::channel::mpsc::unbounded();
+    let task = tokio::spawn(watch.with_status(status).run(stream));
+
+    events
+        .unbounded_send(Ok(Event::Restarted(vec![pod("web-a")])))
+        .unwrap();
+    events
+        .unbounded_send(Ok(Event::Applied(pod("web-b"))))
+        .unwrap();
+    events
+        .unbounded_send(Ok(Event::Deleted(pod("web-a"))))
+        .unwrap();
+    drop(events);
+    task.await.unwrap().unwrap();
+
+    let names = pods.borrow().clone().expect("listed");
+    assert_eq!(names, ["web-b".to_string()].into());
+}
+
+#[tokio::test]
+async fn api_errors_from_the_server_relist_then_stop_the_watch() {
+    use http::{Request, Response};
+    use kpl::podwatch::watcher::spawn_pod_watcher;
+
+    let (service, mut handle) =
+        tower_test::mock::pair::<Request<kube::client::Body>, Response<kube::client::Body>>();
+    let client = kube::Client::new(service, "shop");
+    let status = |code: u16, reason: &str| {
+        let body = serde_json::json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "metadata": {},
+            "status": "Failure",
+            "message": format!("{reason} from the test server"),
+            "reason": reason,
+            "code": code,
+        });
+        Response::builder()
+            .status(code)
+            .body(kube::client::Body::from(body.to_string().into_bytes()))
+            .unwrap()
+    };
+
+    let cli = Cli::parse_from(["kpl", "-n", "shop", "-l", "app=web"]);
+    let config = Config::try_from(cli).expect("valid config");
+    let board = StatusBoard::default();
+    let (tx, _rx) = mpsc::channel(64);
+    let watcher = spawn_pod_watcher(
+        client,
+        "shop".to_string(),
+        config.selector,
+        config.kube,
+        config.enrich,
+        board.clone(),
+        tx,
+    );
+
+    // The list has expired: the watcher lists again after its backoff.
+    let (request, send) = handle.next_request().await.expect("a list request");
+    assert!(request
+        .uri()
+        .query()
+        .unwrap_or_default()
+        .contains("labelSelector=app%3Dweb"));
+    send.send_response(status(410, "Expired"));
+    let (relist, send) = tokio::time::timeout(Duration::from_secs(10), handle.next_request())
+        .await
+        .expect("relisted after a 410")
+        .expect("a list request");
+    assert_eq!(relist.uri().path(), request.uri().path());
+    // Not a failure worth showing.
+    assert_eq!(*board.subscribe_watcher().borrow(), WatcherState::Watching);
+
+    // RBAC said no: retrying won't help.
+    send.send_response(status(403, "Forbidden"));
+    let err = tokio::time::timeout(Duration::from_secs(10), watcher)
+        .await
+        .expect("the watch gives up")
+        .expect("watcher task")
+        .expect_err("403 is fatal")
+        .to_string();
+    assert!(err.contains("Forbidden from the test server"), "{err}");
+}